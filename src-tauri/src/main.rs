@@ -1,24 +1,307 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use log::{error, info, warn};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, ClientConnection, DigitallySignedStruct, SignatureScheme};
 use serde::{Deserialize, Serialize};
+use socket2::{Domain, Socket, Type};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
 use std::fs;
-use std::net::TcpStream;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::fs::OpenOptions;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{
+    IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket,
+};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tauri::{
     image::Image,
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::TrayIconBuilder,
-    Manager, State,
+    Emitter, Manager, State,
 };
+use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_opener::OpenerExt;
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Service {
     name: String,
-    ip: String,
+    #[serde(alias = "ip")]
+    host: String, // IP address or DNS name
     port: String,
+    #[serde(default)]
+    protocol: Protocol,
+    #[serde(default)]
+    pinned: bool, // Always listed first in the tray menu
+    #[serde(default)]
+    timeout_ms: Option<u64>, // Overrides the global default_timeout_ms for this service
+    #[serde(default)]
+    adaptive_timeout: Option<AdaptiveTimeout>, // Replaces timeout_ms once enough latencies are known
+    #[serde(default)]
+    connect_timeout_ms: Option<u64>, // Just the TCP connect, None = timeout_ms
+    #[serde(default)]
+    read_timeout_ms: Option<u64>, // Each read once connected (HTTP, TLS, UDP), None = timeout_ms
+    #[serde(default = "default_true")]
+    enabled: bool, // Disabled services stay listed but are not checked
+    #[serde(default)]
+    udp_payload: String, // Hex-encoded probe for UDP checks, empty by default
+    #[serde(default)]
+    tcp_exchange: Option<TcpExchange>, // TCP checks send this once connected and check the reply
+    #[serde(default, alias = "latency_warn_ms")]
+    degraded_latency_ms: Option<u64>, // Slower successful checks count as degraded
+    #[serde(default, skip_deserializing)]
+    last_checked: Option<u64>, // Unix seconds of the latest check this run, None until then
+    #[serde(default)]
+    interval_secs: Option<u64>, // Overrides the global interval for this service
+    #[serde(default)]
+    expected_body_substring: Option<String>, // HTTP checks only pass if the body contains this
+    #[serde(default)]
+    healthz: Option<HealthzCheck>, // HTTP checks fetch this JSON endpoint and test a value in it
+    #[serde(default)]
+    secondary: Option<CheckStep>, // Must also pass, checked once the service's own check has
+    #[serde(default)]
+    stats: ServiceStats,
+    #[serde(default)]
+    group: Option<String>, // Shown under its own header in the tray menu
+    #[serde(default)]
+    command: String, // Shell command run by command checks, host and port are unused
+    #[serde(default)]
+    maintenance_windows: Vec<MaintenanceWindow>, // Failures in these daily windows aren't alerts
+    #[serde(default)]
+    quiet_hours: Option<QuietHours>, // Checked and recorded as usual, but not alerted on
+    #[serde(default)]
+    description: String, // Longer note for the management window, not shown in the tray
+    #[serde(default = "default_true")]
+    notify: bool, // Desktop notifications and the alert sound
+    #[serde(default = "default_true")]
+    webhook: bool, // Alerts are posted to the webhook, off keeps the service desktop-only
+    #[serde(default = "default_true")]
+    critical: bool, // Counts towards the tray icon under the "critical" health policy
+    #[serde(default, skip_deserializing)]
+    down_since: Option<u64>, // Unix seconds when the current outage began, None while up
+    #[serde(skip)]
+    last_notified: Option<(u64, bool)>, // Runtime only, time and health of the latest alert
+    #[serde(skip)]
+    last_slow_notified: Option<u64>, // Runtime only, when the latest slow alert went out
+}
+
+// A daily span in local time, "HH:MM" to "HH:MM". An end before the start wraps past midnight.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct MaintenanceWindow {
+    start: String,
+    end: String,
+}
+
+// Minutes since midnight for an "HH:MM" time
+fn parse_time_of_day(time: &str) -> Result<u32, String> {
+    let invalid = || format!("'{}' is not a valid time (HH:MM)", time);
+    let (hours, minutes) = time.trim().split_once(':').ok_or_else(invalid)?;
+    match (hours.parse::<u32>(), minutes.parse::<u32>()) {
+        (Ok(h), Ok(m)) if h < 24 && m < 60 && minutes.len() == 2 => Ok(h * 60 + m),
+        _ => Err(invalid()),
+    }
+}
+
+fn validate_maintenance_windows(windows: &[MaintenanceWindow]) -> Result<(), String> {
+    for window in windows {
+        if parse_time_of_day(&window.start)? == parse_time_of_day(&window.end)? {
+            return Err(format!(
+                "Maintenance window {}-{} is empty",
+                window.start, window.end
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Whether the local time of day falls inside any of the windows
+fn in_maintenance(windows: &[MaintenanceWindow]) -> bool {
+    use chrono::Timelike;
+    let now = chrono::Local::now();
+    let minute = now.hour() * 60 + now.minute();
+    windows.iter().any(|window| {
+        match (
+            parse_time_of_day(&window.start),
+            parse_time_of_day(&window.end),
+        ) {
+            (Ok(start), Ok(end)) if start < end => (start..end).contains(&minute),
+            (Ok(start), Ok(end)) => minute >= start || minute < end,
+            _ => false,
+        }
+    })
+}
+
+// A weekly schedule in local time during which a service doesn't alert. Unlike a maintenance
+// window a failure still counts as down, in the tray and the overall health; only the
+// notifications, sound and webhooks wait. A span ending before its start wraps past midnight
+// and belongs to the day it starts on.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct QuietHours {
+    #[serde(default)]
+    days: Vec<String>, // "mon" to "sun", empty = every day
+    start: String,
+    end: String,
+    #[serde(default)]
+    critical_bypass: bool, // Services marked critical alert anyway
+}
+
+fn validate_quiet_hours(quiet: &QuietHours) -> Result<(), String> {
+    if parse_time_of_day(&quiet.start)? == parse_time_of_day(&quiet.end)? {
+        return Err(format!(
+            "Quiet hours {}-{} are empty",
+            quiet.start, quiet.end
+        ));
+    }
+    for day in &quiet.days {
+        day.parse::<chrono::Weekday>()
+            .map_err(|_| format!("'{}' is not a day of the week", day))?;
+    }
+    Ok(())
+}
+
+// Whether the given local weekday and minute of the day fall inside the quiet hours
+fn in_quiet_hours(quiet: &QuietHours, weekday: chrono::Weekday, minute: u32) -> bool {
+    let (Ok(start), Ok(end)) = (
+        parse_time_of_day(&quiet.start),
+        parse_time_of_day(&quiet.end),
+    ) else {
+        return false;
+    };
+    let on = |day: chrono::Weekday| {
+        quiet.days.is_empty()
+            || quiet
+                .days
+                .iter()
+                .any(|d| d.parse::<chrono::Weekday>() == Ok(day))
+    };
+    if start < end {
+        on(weekday) && (start..end).contains(&minute)
+    } else {
+        (on(weekday) && minute >= start) || (on(weekday.pred()) && minute < end)
+    }
+}
+
+// Whether a service's alerts are held back by its quiet hours right now
+fn quiet_now(service: &Service) -> bool {
+    use chrono::{Datelike, Timelike};
+    let Some(quiet) = &service.quiet_hours else {
+        return false;
+    };
+    if quiet.critical_bypass && service.critical {
+        return false;
+    }
+    let now = chrono::Local::now();
+    in_quiet_hours(quiet, now.weekday(), now.hour() * 60 + now.minute())
+}
+
+// Running totals of a service's checks, kept across restarts
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+struct ServiceStats {
+    total_checks: u64,
+    failures: u64,
+    current_streak: i64, // Consecutive ups when positive, consecutive downs when negative
+}
+
+impl ServiceStats {
+    fn record(&mut self, healthy: bool) {
+        self.total_checks += 1;
+        if healthy {
+            self.current_streak = self.current_streak.max(0) + 1;
+        } else {
+            self.failures += 1;
+            self.current_streak = self.current_streak.min(0) - 1;
+        }
+    }
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Service {
+            name: String::new(),
+            host: String::new(),
+            port: String::new(),
+            protocol: Protocol::default(),
+            pinned: false,
+            timeout_ms: None,
+            connect_timeout_ms: None,
+            read_timeout_ms: None,
+            enabled: true,
+            udp_payload: String::new(),
+            tcp_exchange: None,
+            degraded_latency_ms: None,
+            last_checked: None,
+            interval_secs: None,
+            expected_body_substring: None,
+            healthz: None,
+            secondary: None,
+            adaptive_timeout: None,
+            stats: ServiceStats::default(),
+            group: None,
+            command: String::new(),
+            maintenance_windows: Vec::new(),
+            quiet_hours: None,
+            description: String::new(),
+            notify: true,
+            webhook: true,
+            critical: true,
+            down_since: None,
+            last_notified: None,
+            last_slow_notified: None,
+        }
+    }
+}
+
+// How a service is checked
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Protocol {
+    #[default]
+    Tcp, // Port accepts a connection
+    Http, // GET / answers 2xx/3xx
+    Https,
+    Ping,    // ICMP echo, port is ignored
+    Udp,     // Any reply to a probe datagram
+    Tls,     // TLS handshake, down when the certificate is close to expiry
+    Command, // Shell command exits with 0, needs allow_command_checks
+}
+
+// Up when the check fully succeeded, Degraded when it succeeded but slowly or with a
+// redirect, Down when it failed, Maintenance when it failed inside a maintenance window
+#[derive(Clone, Copy, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum HealthState {
+    Up,
+    Degraded,
+    Down,
+    Maintenance,
+}
+
+// Outcome of checking one service in a cycle
+#[derive(Clone, Serialize, Debug)]
+struct CheckResult {
+    service: Service,
+    healthy: bool, // Up, degraded or in maintenance
+    state: HealthState,
+    latency_ms: Option<u128>,     // None when the check failed
+    status_code: Option<u16>,     // HTTP checks only
+    error: Option<String>,        // Why the check could not be performed, or a body mismatch
+    cert_expires_at: Option<u64>, // TLS checks only, unix seconds of the certificate's notAfter
+    injected: bool, // Checked with a fault injection, so test data rather than a real result
+    output: Option<String>, // Command checks only, what the command printed
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -26,251 +309,6434 @@ struct AppStateData {
     services: Vec<Service>,
     interval_secs: u64,
     #[serde(default = "default_icon_set")]
-    icon_set: String, // "default" or "alt"
+    icon_set: String, // "default", "alt" or a theme found in the icons directory
+    #[serde(default)]
+    template_mode: Option<bool>, // Render the tray icon as a macOS template, None = only for "alt"
+    #[serde(default)]
+    services_source_file: Option<String>, // When set, services are read from this file
+    #[serde(default = "default_true")]
+    tray_show_all: bool, // When off, the tray lists only problems and sums up healthy services
+    #[serde(default)]
+    menu_name_max_len: Option<usize>, // Elide longer names in the tray menu, None = no limit
+    #[serde(default)]
+    stabilization_secs: u64, // Stay amber this long after recovering before going green
+    #[serde(default)]
+    verbose_logging: bool, // Log full saved content, which may contain secrets
+    #[serde(default = "default_true")]
+    notifications_enabled: bool, // Desktop notification when a service goes down or recovers
+    #[serde(default = "default_notification_min_interval_secs")]
+    notification_min_interval_secs: u64, // At most one alert per service this often, 0 = no limit
+    #[serde(default)]
+    snooze_until: Option<u64>, // Notifications are muted until this unix time
+    #[serde(default)]
+    allow_command_checks: bool, // Command services only run when this is set
+    #[serde(default)]
+    sound_enabled: bool, // Play an alert sound when a service goes down
+    #[serde(default)]
+    webhook_url: Option<String>, // Receives a JSON POST whenever a service goes down or recovers
+    #[serde(default)]
+    webhook_template: Option<String>, // JSON body with {{field}} placeholders, None = the default
+    #[serde(default)]
+    update_url: Option<String>, // JSON release manifest checked on startup, None = no update checks
+    #[serde(default)]
+    autostart: bool, // Launch at login; the OS registration follows this
+    #[serde(default)]
+    monitoring_paused: bool, // No checks run at all until resumed
+    #[serde(default)]
+    allow_duplicates: bool, // Allow several services on the same host and port
+    #[serde(default)]
+    health_policy: HealthPolicy,
+    #[serde(default)]
+    structured_event_output: EventOutput,
+    #[serde(default = "new_agent_id")]
+    agent_id: String, // Identifies this install in structured events
+    #[serde(default = "default_timeout_ms")]
+    default_timeout_ms: u64, // Check timeout for services without their own timeout_ms
+    #[serde(default = "default_cert_warn_days")]
+    cert_warn_days: u64, // TLS checks go down when the certificate expires sooner than this
+    #[serde(default = "default_retry_count")]
+    retry_count: u32, // Extra attempts before a failing service is marked down
+    #[serde(default = "default_retry_delay_ms")]
+    retry_delay_ms: u64,
+    #[serde(default = "default_max_concurrent_checks")]
+    max_concurrent_checks: usize, // Caps simultaneous checks, and so open sockets
+    #[serde(default = "default_down_recheck_secs")]
+    down_recheck_secs: u64, // Down services are re-checked this often until they recover, 0 = off
+    #[serde(default)]
+    check_jitter_ms: u64, // Each check waits a random 0..=this first, so they don't all fire at once
+    #[serde(default)]
+    api_enabled: bool, // Serve status on 127.0.0.1:api_port for external tooling
+    #[serde(default = "default_api_port")]
+    api_port: u16,
+    #[serde(default)]
+    window_geometry: Option<WindowGeometry>, // Where the management window was last closed
     #[serde(skip)]
     is_healthy: bool, // Runtime only, defaults to true
+    #[serde(skip)]
+    first_run: bool, // Runtime only, no settings file existed at startup
+    #[serde(skip)]
+    settings_backup: Option<String>, // Runtime only, where an unreadable settings file was moved
+    #[serde(skip)]
+    services_source_error: Option<String>, // Runtime only, last failed reload of the source file
+    #[serde(skip)]
+    mirrors: HashMap<ServiceKey, Mirror>, // Runtime only
+    #[serde(skip)]
+    faults: HashMap<ServiceKey, FaultInjection>, // Runtime only
+    #[serde(skip)]
+    latency_samples: HashMap<ServiceKey, VecDeque<u64>>, // Runtime only, newest last
+    #[serde(skip)]
+    escalations: HashMap<ServiceKey, Escalation>, // Runtime only, never survives a restart
+    #[serde(skip)]
+    edit_txn: Option<EditTxn>, // Runtime only, the open edit transaction if any
+    #[serde(skip)]
+    last_txn_id: u64,
+    #[serde(skip)]
+    last_results: Vec<CheckResult>, // Runtime only, results of the latest cycle
+    #[serde(skip)]
+    last_check_ts: Option<u64>, // Runtime only, unix seconds of the latest cycle
+    #[serde(skip)]
+    cycle_revision: u64, // Runtime only, counts the cycles that stored results
+    #[serde(skip)]
+    stats_saved_at: u64, // Runtime only, unix seconds of the latest save after a cycle
+    #[serde(skip)]
+    stabilizing_since: Option<u64>, // Runtime only, when the last down service recovered
+}
+
+#[derive(Serialize, Debug)]
+struct FdUsage {
+    open: Option<usize>,
+    soft_limit: Option<u64>,
+}
+
+// Warn once open descriptors pass this share of the soft limit
+const FD_WARN_RATIO: f64 = 0.8;
+
+// Settings files from before template_mode kept the old coupling to the alt set
+fn template_mode(data: &AppStateData) -> bool {
+    data.template_mode.unwrap_or(data.icon_set == "alt")
+}
+
+// Outer position and inner size of the management window, in physical pixels
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct WindowGeometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+// What the tray icon shows
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum TrayStatus {
+    Healthy,
+    Degraded,
+    Stabilizing,
+    Unhealthy,
+    Paused,
+}
+
+fn tray_status(data: &AppStateData) -> TrayStatus {
+    if data.monitoring_paused {
+        TrayStatus::Paused
+    } else if !data.is_healthy {
+        TrayStatus::Unhealthy
+    } else if data.stabilizing_since.is_some() {
+        TrayStatus::Stabilizing
+    } else if data
+        .last_results
+        .iter()
+        .any(|r| r.state == HealthState::Degraded)
+    {
+        TrayStatus::Degraded
+    } else {
+        TrayStatus::Healthy
+    }
+}
+
+// Which down services turn the tray red
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+enum HealthPolicy {
+    #[default]
+    Any, // Any service is down
+    All,      // Every service is down, e.g. redundant mirrors
+    Critical, // Any service marked critical is down
+}
+
+fn overall_healthy(results: &[CheckResult], policy: HealthPolicy) -> bool {
+    match policy {
+        HealthPolicy::Any => results.iter().all(|r| r.healthy),
+        HealthPolicy::All => results.is_empty() || results.iter().any(|r| r.healthy),
+        HealthPolicy::Critical => results
+            .iter()
+            .filter(|r| r.service.critical)
+            .all(|r| r.healthy),
+    }
+}
+
+// Applies a finished cycle's overall health, holding amber after a recovery until it has held
+fn record_overall_health(data: &mut AppStateData, is_overall_healthy: bool) {
+    let now = now_secs();
+    if !is_overall_healthy {
+        // Anything dropping (again) cancels stabilization
+        data.stabilizing_since = None;
+    } else if !data.is_healthy && data.stabilization_secs > 0 {
+        data.stabilizing_since = Some(now);
+    } else if let Some(since) = data.stabilizing_since {
+        if now.saturating_sub(since) >= data.stabilization_secs {
+            data.stabilizing_since = None;
+        }
+    }
+    data.is_healthy = is_overall_healthy;
+}
+
+// Text a TCP check sends once connected, e.g. "PING\r\n". The service is only up if the
+// reply contains `expect`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct TcpExchange {
+    send: Cow<'static, str>,
+    expect: Cow<'static, str>,
+}
+
+// A preset for a common kind of service
+#[derive(Serialize, Debug)]
+struct CheckTemplate {
+    name: &'static str,
+    port: u16,
+    protocol: Protocol,
+    exchange: Option<TcpExchange>, // None for a bare connect
+    description: &'static str,
+}
+
+const CHECK_TEMPLATES: &[CheckTemplate] = &[
+    CheckTemplate {
+        name: "PostgreSQL",
+        port: 5432,
+        protocol: Protocol::Tcp,
+        exchange: None,
+        description: "PostgreSQL database server",
+    },
+    CheckTemplate {
+        name: "MySQL",
+        port: 3306,
+        protocol: Protocol::Tcp,
+        exchange: None,
+        description: "MySQL / MariaDB database server",
+    },
+    CheckTemplate {
+        name: "Redis",
+        port: 6379,
+        protocol: Protocol::Tcp,
+        exchange: Some(TcpExchange {
+            send: Cow::Borrowed("PING\r\n"),
+            expect: Cow::Borrowed("+PONG"),
+        }),
+        description: "Redis key-value store, answers PING with PONG",
+    },
+    CheckTemplate {
+        name: "MongoDB",
+        port: 27017,
+        protocol: Protocol::Tcp,
+        exchange: None,
+        description: "MongoDB database server",
+    },
+    CheckTemplate {
+        name: "HTTP website",
+        port: 80,
+        protocol: Protocol::Http,
+        exchange: None,
+        description: "Plain HTTP web server",
+    },
+    CheckTemplate {
+        name: "HTTPS website",
+        port: 443,
+        protocol: Protocol::Https,
+        exchange: None,
+        description: "HTTPS web server",
+    },
+    CheckTemplate {
+        name: "SSH",
+        port: 22,
+        protocol: Protocol::Tcp,
+        exchange: None,
+        description: "SSH server",
+    },
+    CheckTemplate {
+        name: "DNS",
+        port: 53,
+        protocol: Protocol::Tcp,
+        exchange: None,
+        description: "DNS server (TCP)",
+    },
+    CheckTemplate {
+        name: "SMTP",
+        port: 587,
+        protocol: Protocol::Tcp,
+        exchange: None,
+        description: "Mail submission server",
+    },
+    CheckTemplate {
+        name: "RDP",
+        port: 3389,
+        protocol: Protocol::Tcp,
+        exchange: None,
+        description: "Windows Remote Desktop",
+    },
+];
+
+// Artificial latency/loss applied to a service's checks, for exercising failure handling
+#[derive(Clone, Debug)]
+struct FaultInjection {
+    added_latency_ms: u64,
+    loss_pct: f64,
+}
+
+// Alert channels a service can be escalated to for a while, e.g. during an incident
+#[derive(Clone, Copy, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum AlertChannel {
+    Desktop,
+    Sound,
+    Webhook,
+}
+
+// Temporarily adds alert channels to a service, until `until` (unix seconds)
+#[derive(Clone, Debug)]
+struct Escalation {
+    channels: Vec<AlertChannel>,
+    until: u64,
+}
+
+// One line of history.jsonl: a service's health at the end of a cycle
+#[derive(Serialize, Deserialize, Debug)]
+struct HistoryEntry {
+    ts: u64,
+    name: String,
+    healthy: bool,
+}
+
+// History older than this is pruned on startup
+const HISTORY_RETENTION_SECS: u64 = 7 * 24 * 60 * 60;
+
+// Number of side-by-side samples kept per mirrored service
+const MIRROR_HISTORY: usize = 100;
+
+// Name, host and port: what same_service compares. Runtime state that outlives a cycle is
+// keyed by this rather than by position, which changes when services are removed or moved.
+type ServiceKey = (String, String, String);
+
+fn service_key(service: &Service) -> ServiceKey {
+    (
+        service.name.clone(),
+        service.host.clone(),
+        service.port.clone(),
+    )
+}
+
+fn service_key_at(data: &AppStateData, index: usize) -> Result<ServiceKey, String> {
+    data.services
+        .get(index)
+        .map(service_key)
+        .ok_or_else(|| "Index out of bounds".to_string())
+}
+
+// Forgets runtime extras of services that were removed, or edited into a different one
+fn drop_orphaned_overrides(data: &mut AppStateData) {
+    let keys: Vec<ServiceKey> = data.services.iter().map(service_key).collect();
+    data.mirrors.retain(|key, _| keys.contains(key));
+    data.faults.retain(|key, _| keys.contains(key));
+    data.latency_samples.retain(|key, _| keys.contains(key));
+    let now = now_secs();
+    data.escalations
+        .retain(|key, escalation| keys.contains(key) && escalation.until > now);
+}
+
+// Whether the service alerts on the channel, on its own settings or through an escalation
+fn alerts_on(
+    escalations: &HashMap<ServiceKey, Escalation>,
+    service: &Service,
+    channel: AlertChannel,
+    now: u64,
+) -> bool {
+    let own = match channel {
+        AlertChannel::Desktop | AlertChannel::Sound => service.notify,
+        AlertChannel::Webhook => service.webhook,
+    };
+    own || escalations
+        .get(&service_key(service))
+        .is_some_and(|e| e.until > now && e.channels.contains(&channel))
+}
+
+// Successful checks whose latency is kept per service for percentiles
+const LATENCY_WINDOW: usize = 100;
+
+fn record_latencies(samples: &mut HashMap<ServiceKey, VecDeque<u64>>, results: &[CheckResult]) {
+    for result in results.iter().filter(|r| !r.injected) {
+        let Some(latency) = result.latency_ms else {
+            continue;
+        };
+        let window = samples.entry(service_key(&result.service)).or_default();
+        if window.len() == LATENCY_WINDOW {
+            window.pop_front();
+        }
+        window.push_back(latency as u64);
+    }
+}
+
+// A timeout that follows the service's usual latency: factor × p95 of the latency window,
+// kept within min_ms..=max_ms
+#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+struct AdaptiveTimeout {
+    factor: f64,
+    min_ms: u64,
+    max_ms: u64,
+}
+
+// Until a service has this many latencies its static timeout applies
+const MIN_ADAPTIVE_SAMPLES: usize = 20;
+
+fn validate_adaptive_timeout(adaptive: &AdaptiveTimeout) -> Result<(), String> {
+    if !(adaptive.factor.is_finite() && adaptive.factor >= 1.0) {
+        return Err("The timeout factor must be at least 1".to_string());
+    }
+    if adaptive.min_ms == 0 || adaptive.min_ms > adaptive.max_ms {
+        return Err("The timeout bounds must satisfy 1 <= min_ms <= max_ms".to_string());
+    }
+    Ok(())
+}
+
+// The adaptive timeout in ms for a latency window, or None while it has too few samples
+fn adaptive_timeout_ms(adaptive: &AdaptiveTimeout, window: Option<&VecDeque<u64>>) -> Option<u64> {
+    let mut sorted: Vec<u64> = window.into_iter().flatten().copied().collect();
+    if sorted.len() < MIN_ADAPTIVE_SAMPLES {
+        return None;
+    }
+    sorted.sort_unstable();
+    let p95 = percentile(&sorted, 95.0)?;
+    let scaled = (adaptive.factor * p95 as f64).ceil() as u64;
+    Some(scaled.clamp(adaptive.min_ms, adaptive.max_ms))
+}
+
+// Nearest-rank percentile of an ascending list, None when it is empty
+fn percentile(sorted: &[u64], pct: f64) -> Option<u64> {
+    let rank = (pct / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.clamp(1, sorted.len().max(1)) - 1).copied()
+}
+
+// A temporary second target checked alongside a service, e.g. while migrating hosts
+#[derive(Clone, Debug)]
+struct Mirror {
+    old_ip: String,
+    old_port: String,
+    new_ip: String,
+    new_port: String,
+    samples: VecDeque<MirrorSample>,
+}
+
+#[derive(Clone, Serialize, Debug)]
+struct MirrorSample {
+    timestamp: u64,
+    old_healthy: bool,
+    new_healthy: bool,
+    old_latency_ms: Option<u128>,
+    new_latency_ms: Option<u128>,
 }
 
 fn default_icon_set() -> String {
     "default".to_string()
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_cert_warn_days() -> u64 {
+    14
+}
+
+fn default_retry_count() -> u32 {
+    2
+}
+
+fn default_retry_delay_ms() -> u64 {
+    500
+}
+
+fn default_max_concurrent_checks() -> usize {
+    16
+}
+
+fn default_notification_min_interval_secs() -> u64 {
+    60
+}
+
+fn default_api_port() -> u16 {
+    9797
+}
+
+fn default_down_recheck_secs() -> u64 {
+    5
+}
+
+fn new_agent_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+// Where machine-readable events go, separate from the human log
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+enum EventOutput {
+    #[default]
+    Off,
+    Stdout,
+    File,
+}
+
+// One line of structured output. The schema is documented in README.md; keep it stable
+// and never put addresses or other configuration values in it.
+#[derive(Serialize, Debug)]
+struct StructuredEvent<'a> {
+    ts: u64,
+    level: &'a str,
+    service: Option<&'a str>,
+    event: &'a str,
+    reason: Option<&'a str>,
+    latency_ms: Option<u128>,
+    injected: bool,
+    agent_id: &'a str,
+}
+
+// An open edit transaction. Its changes aren't saved while it is open, so the settings file
+// still holds what `snapshot` does; aborting or expiring goes back to it. Untagged changes
+// made meanwhile go into both the staged settings and the snapshot.
+#[derive(Clone, Debug)]
+struct EditTxn {
+    id: u64,
+    last_used: u64, // Unix seconds of the latest command in the transaction
+    snapshot: Box<AppStateData>,
+    untagged_base: Option<serde_json::Value>, // The settings before the current untagged change
+}
+
+// An edit transaction unused for this long is rolled back, e.g. after its client crashed
+const EDIT_TXN_TIMEOUT_SECS: u64 = 120;
+
 // Global state now includes the persistence path
 struct AppState {
     data: Arc<Mutex<AppStateData>>,
     file_path: Arc<Mutex<PathBuf>>,
+    source_watcher: Mutex<Option<RecommendedWatcher>>,
+    running: Arc<AtomicBool>, // Cleared on quit to stop the monitoring loop
+    api_server: Mutex<Option<ApiServer>>, // The local API while it is enabled
+}
+
+#[derive(Serialize)]
+struct ServicesSource {
+    path: Option<String>,
+    error: Option<String>,
+}
+
+// Describes saved state without any field values, so secrets never reach the log
+fn state_summary(data: &AppStateData, json: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    json.hash(&mut hasher);
+    format!(
+        "{} services, {} bytes, hash {:016x}",
+        data.services.len(),
+        json.len(),
+        hasher.finish()
+    )
+}
+
+// Writes to a temporary file next to `path` and renames it into place, so a crash
+// mid-write leaves either the old or the new file, never a truncated one
+fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = fs::File::create(&tmp_path)
+        .and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+// A panic while a lock is held poisons it. The state is plain data that stays usable, so
+// recover it instead of failing every later command.
+fn lock_or_recover<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        error!("Recovering state after a panic while it was locked");
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
 }
 
 // Helper to save state
-fn save_state(data: &AppStateData, path: &PathBuf) {
+fn save_state(data: &AppStateData, path: &Path) -> Result<(), String> {
     // println!("Saving state to {:?}", path);
-    match serde_json::to_string_pretty(data) {
-        Ok(json) => {
-            if let Err(e) = fs::write(path, &json) {
-                println!("Failed to write state: {}", e);
-            } else {
-                println!("State saved. Content: {}", json);
+    let json = serde_json::to_string_pretty(data)
+        .map_err(|e| format!("Failed to serialize state: {}", e))?;
+    write_atomic(path, &json).map_err(|e| {
+        let message = format!("Failed to write settings to {}: {}", path.display(), e);
+        error!("{}", message);
+        message
+    })?;
+
+    if data.verbose_logging {
+        info!("State saved. Content: {}", json);
+    } else {
+        info!("State saved: {}", state_summary(data, &json));
+    }
+    Ok(())
+}
+
+// Moves an unparseable settings file aside as settings.json.bak-<unix seconds>
+fn backup_corrupt_settings(path: &Path) -> Result<PathBuf, String> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".bak-{}", now_secs()));
+    let backup = PathBuf::from(backup);
+    fs::rename(path, &backup)
+        .map_err(|e| format!("Failed to back up corrupted settings: {}", e))?;
+    Ok(backup)
+}
+
+fn history_path(settings_path: &Path) -> PathBuf {
+    settings_path.with_file_name("history.jsonl")
+}
+
+// Running build, for bug reports and update checks
+#[derive(Serialize)]
+struct AppInfo {
+    name: String,
+    version: String,
+    profile: &'static str, // "debug" or "release"
+    os: &'static str,
+    arch: &'static str,
+}
+
+#[tauri::command]
+fn get_app_info(app: tauri::AppHandle) -> Result<AppInfo, String> {
+    let package = app.package_info();
+    Ok(AppInfo {
+        name: package.name.clone(),
+        version: package.version.to_string(),
+        profile: if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        },
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+    })
+}
+
+// Registers or unregisters the app as an OS login item, if it isn't already
+fn apply_autostart(app: &tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let launcher = app.autolaunch();
+    let registered = launcher
+        .is_enabled()
+        .map_err(|e| format!("Failed to read autostart state: {}", e))?;
+    if registered == enabled {
+        return Ok(());
+    }
+    let result = if enabled {
+        launcher.enable()
+    } else {
+        launcher.disable()
+    };
+    result.map_err(|e| format!("Failed to update autostart: {}", e))
+}
+
+#[tauri::command]
+fn set_autostart(
+    app: tauri::AppHandle,
+    state: State<AppState>,
+    enabled: bool,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    apply_autostart(&app, enabled)?;
+    data.autostart = enabled;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+// Whether the app is registered to launch at login, as the OS sees it
+#[tauri::command]
+fn get_autostart(app: tauri::AppHandle) -> Result<bool, String> {
+    app.autolaunch()
+        .is_enabled()
+        .map_err(|e| format!("Failed to read autostart state: {}", e))
+}
+
+// Where settings.json lives, for backups or inspecting it by hand
+#[tauri::command]
+fn get_config_path(state: State<AppState>) -> Result<String, String> {
+    let path = lock_or_recover(&state.file_path);
+    Ok(path.to_string_lossy().into_owned())
+}
+
+// Overrides where settings.json is read from and saved to
+const CONFIG_PATH_ENV: &str = "UPTIME_CONFIG_PATH";
+
+// The log file rotates at this size, keeping the 5 most recent files
+const LOG_FILE_NAME: &str = "uptime-watcher";
+const LOG_MAX_BYTES: u128 = 1_000_000;
+
+#[tauri::command]
+fn get_log_path(state: State<AppState>) -> Result<String, String> {
+    let path = lock_or_recover(&state.file_path);
+    let log_path = path.with_file_name(format!("{}.log", LOG_FILE_NAME));
+    Ok(log_path.to_string_lossy().into_owned())
+}
+
+// Appends one history line per service for a finished cycle
+fn append_history(path: &Path, results: &[CheckResult]) {
+    let ts = now_secs();
+    let mut lines = String::new();
+    // Fault injection is for exercising the app, its results aren't real uptime
+    for result in results.iter().filter(|r| !r.injected) {
+        let entry = HistoryEntry {
+            ts,
+            name: result.service.name.clone(),
+            healthy: result.healthy,
+        };
+        if let Ok(line) = serde_json::to_string(&entry) {
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+    }
+
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()));
+    if let Err(e) = written {
+        error!("Failed to write history: {}", e);
+    }
+}
+
+// Yields the parseable history entries; unreadable lines are skipped
+fn read_history(path: &Path) -> Vec<HistoryEntry> {
+    fs::read_to_string(path)
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Drops history entries older than the retention period
+fn prune_history(path: &Path) {
+    if !path.exists() {
+        return;
+    }
+    let cutoff = now_secs().saturating_sub(HISTORY_RETENTION_SECS);
+    let mut kept = String::new();
+    let mut pruned = 0;
+    for entry in read_history(path) {
+        if entry.ts < cutoff {
+            pruned += 1;
+        } else if let Ok(line) = serde_json::to_string(&entry) {
+            kept.push_str(&line);
+            kept.push('\n');
+        }
+    }
+    if pruned > 0 {
+        match fs::write(path, kept) {
+            Ok(()) => info!("Pruned {} old history entries", pruned),
+            Err(e) => error!("Failed to prune history: {}", e),
+        }
+    }
+}
+
+// Reads and validates a services file (a JSON array of services)
+fn read_services_file(path: &Path) -> Result<Vec<Service>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let services: Vec<Service> = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid services file {}: {}", path.display(), e))?;
+    check_service_list(&services)
+        .map_err(|e| format!("Invalid services file {}: {}", path.display(), e))?;
+
+    Ok(services)
+}
+
+// Sanity checks for a list of services loaded in bulk, without resolving any hosts
+fn check_service_list(services: &[Service]) -> Result<(), String> {
+    for (i, service) in services.iter().enumerate() {
+        if service.name.trim().is_empty() {
+            return Err(format!("Service #{} has an empty name", i + 1));
+        }
+        if service.protocol == Protocol::Command {
+            if service.command.trim().is_empty() {
+                return Err(format!("Service '{}' has an empty command", service.name));
+            }
+        } else if service.protocol != Protocol::Ping
+            && !matches!(service.port.parse::<u16>(), Ok(port) if port > 0)
+        {
+            return Err(format!(
+                "Service '{}' has an invalid port '{}'",
+                service.name, service.port
+            ));
+        }
+        if let Some(secs) = service.interval_secs {
+            validate_interval(secs).map_err(|e| format!("Service '{}': {}", service.name, e))?;
+        }
+        validate_maintenance_windows(&service.maintenance_windows)
+            .map_err(|e| format!("Service '{}': {}", service.name, e))?;
+        if let Some(quiet) = &service.quiet_hours {
+            validate_quiet_hours(quiet)
+                .map_err(|e| format!("Service '{}': {}", service.name, e))?;
+        }
+        if let Some(healthz) = &service.healthz {
+            validate_healthz(healthz).map_err(|e| format!("Service '{}': {}", service.name, e))?;
+        }
+        if let Some(exchange) = &service.tcp_exchange {
+            validate_tcp_exchange(exchange)
+                .map_err(|e| format!("Service '{}': {}", service.name, e))?;
+        }
+        if let Some(step) = &service.secondary {
+            validate_check_step(step).map_err(|e| format!("Service '{}': {}", service.name, e))?;
+        }
+        if let Some(adaptive) = &service.adaptive_timeout {
+            validate_adaptive_timeout(adaptive)
+                .map_err(|e| format!("Service '{}': {}", service.name, e))?;
+        }
+    }
+    Ok(())
+}
+
+// Reloads services from the source file. An invalid file keeps the last good list.
+fn reload_services_source(data: &Mutex<AppStateData>, path: &Path) {
+    let result = read_services_file(path);
+    let mut data = lock_or_recover(data);
+    match result {
+        Ok(mut services) => {
+            info!("Loaded {} services from {:?}", services.len(), path);
+            // Services that are still listed keep their stats
+            for service in services.iter_mut() {
+                if let Some(old) = data.services.iter().find(|s| same_service(s, service)) {
+                    service.stats = old.stats;
+                    service.down_since = old.down_since;
+                }
             }
+            data.services = services;
+            data.services_source_error = None;
+        }
+        Err(e) => {
+            warn!("Keeping last good services: {}", e);
+            data.services_source_error = Some(e);
+        }
+    }
+}
+
+// Watches the source file's directory (editors often replace files on save)
+fn watch_services_source(
+    data: Arc<Mutex<AppStateData>>,
+    path: PathBuf,
+) -> Result<RecommendedWatcher, String> {
+    let dir = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .to_path_buf();
+    let file_name = path.file_name().map(|n| n.to_os_string());
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            let touches_file = event
+                .paths
+                .iter()
+                .any(|p| p.file_name().map(|n| n.to_os_string()) == file_name);
+            if touches_file && !event.kind.is_access() {
+                reload_services_source(&data, &path);
+            }
+        }
+    })
+    .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+
+    Ok(watcher)
+}
+
+// Mutations tagged with a transaction id must belong to the currently open edit. Untagged
+// ones go ahead while an edit is open; the settings they start from are kept so persist can
+// tell what they changed.
+fn check_edit_txn(data: &mut AppStateData, txn_id: Option<u64>) -> Result<(), String> {
+    expire_stale_edit(data);
+    let untagged_base = match (txn_id, &data.edit_txn) {
+        (None, Some(_)) => Some(settings_json(data)?),
+        _ => None,
+    };
+    match (txn_id, data.edit_txn.as_mut()) {
+        (Some(id), Some(txn)) if txn.id == id => {
+            txn.last_used = now_secs();
+            Ok(())
+        }
+        (Some(id), _) => Err(format!("Unknown edit transaction {}", id)),
+        (None, Some(txn)) => {
+            txn.untagged_base = untagged_base;
+            Ok(())
+        }
+        (None, None) => Ok(()),
+    }
+}
+
+// Saves a mutation right away, unless it belongs to an edit transaction, which saves once on
+// commit_edit. An untagged mutation made while an edit is open is applied to the edit's
+// snapshot too, and the snapshot is what gets saved, so the staged changes stay unsaved.
+fn persist(state: &AppState, data: &mut AppStateData, txn_id: Option<u64>) -> Result<(), String> {
+    if txn_id.is_some() {
+        return Ok(());
+    }
+    let path = lock_or_recover(&state.file_path);
+    let Some(txn) = &data.edit_txn else {
+        return save_state(data, &path);
+    };
+    let Some(base) = &txn.untagged_base else {
+        return Ok(());
+    };
+    let snapshot = with_changes(&txn.snapshot, base, data)?;
+    save_state(&snapshot, &path)?;
+    if let Some(txn) = data.edit_txn.as_mut() {
+        *txn.snapshot = snapshot;
+        txn.untagged_base = None;
+    }
+    Ok(())
+}
+
+fn settings_json(data: &AppStateData) -> Result<serde_json::Value, String> {
+    serde_json::to_value(data).map_err(|e| format!("Failed to serialize state: {}", e))
+}
+
+// `target` with whatever changed from `base` (settings JSON) to `after` applied on top
+fn with_changes(
+    target: &AppStateData,
+    base: &serde_json::Value,
+    after: &AppStateData,
+) -> Result<AppStateData, String> {
+    let mut merged = settings_json(target)?;
+    merge_changes(base, &settings_json(after)?, &mut merged);
+    serde_json::from_value(merged)
+        .map_err(|e| format!("Failed to merge the change into the open edit: {}", e))
+}
+
+// Applies what changed from `base` to `after` onto `target`, leaving the rest of `target`
+// alone. Objects merge field by field and services by name, host and port; any other value
+// that changed is replaced whole.
+fn merge_changes(
+    base: &serde_json::Value,
+    after: &serde_json::Value,
+    target: &mut serde_json::Value,
+) {
+    use serde_json::Value;
+    match (base, after, &mut *target) {
+        (Value::Object(base), Value::Object(after), Value::Object(target)) => {
+            for (key, new) in after {
+                let old = base.get(key);
+                if old == Some(new) {
+                    continue;
+                }
+                match (old, target.get_mut(key)) {
+                    (Some(old), Some(current)) if key == "services" => {
+                        merge_services(old, new, current)
+                    }
+                    (Some(old), Some(current)) => merge_changes(old, new, current),
+                    _ => {
+                        target.insert(key.clone(), new.clone());
+                    }
+                }
+            }
+            for key in base.keys().filter(|key| !after.contains_key(*key)) {
+                target.remove(key);
+            }
+        }
+        _ => *target = after.clone(),
+    }
+}
+
+fn merge_services(
+    base: &serde_json::Value,
+    after: &serde_json::Value,
+    target: &mut serde_json::Value,
+) {
+    use serde_json::Value;
+    let (Value::Array(base), Value::Array(after), Value::Array(current)) =
+        (base, after, &mut *target)
+    else {
+        *target = after.clone();
+        return;
+    };
+    let key = |service: &Value| {
+        ["name", "host", "port"].map(|field| service.get(field).cloned().unwrap_or(Value::Null))
+    };
+    // Removed services go, unless the edit already removed them
+    current.retain(|service| {
+        let k = key(service);
+        !base.iter().any(|b| key(b) == k) || after.iter().any(|a| key(a) == k)
+    });
+    for new in after {
+        let k = key(new);
+        match base.iter().find(|b| key(b) == k) {
+            Some(old) if old == new => {}
+            Some(old) => {
+                // A service the edit removed stays removed
+                if let Some(service) = current.iter_mut().find(|s| key(s) == k) {
+                    merge_changes(old, new, service);
+                }
+            }
+            None => {
+                if !current.iter().any(|s| key(s) == k) {
+                    current.push(new.clone());
+                }
+            }
+        }
+    }
+}
+
+// Moves the state that is never saved from `old` into `fresh`, a config just read from
+// JSON. Services listed in both keep their check timing and outage.
+fn carry_runtime_state(old: &mut AppStateData, fresh: &mut AppStateData) {
+    for service in fresh.services.iter_mut() {
+        if let Some(current) = old.services.iter().find(|s| same_service(s, service)) {
+            service.last_checked = current.last_checked;
+            service.down_since = current.down_since;
+            service.last_notified = current.last_notified;
+            service.last_slow_notified = current.last_slow_notified;
+        }
+    }
+    fresh.is_healthy = old.is_healthy;
+    fresh.first_run = old.first_run;
+    fresh.settings_backup = old.settings_backup.take();
+    fresh.services_source_error = old.services_source_error.take();
+    fresh.mirrors = std::mem::take(&mut old.mirrors);
+    fresh.faults = std::mem::take(&mut old.faults);
+    fresh.latency_samples = std::mem::take(&mut old.latency_samples);
+    fresh.escalations = std::mem::take(&mut old.escalations);
+    fresh.edit_txn = old.edit_txn.take();
+    fresh.last_txn_id = old.last_txn_id;
+    fresh.last_results = std::mem::take(&mut old.last_results);
+    fresh.last_check_ts = old.last_check_ts;
+    fresh.cycle_revision = old.cycle_revision;
+    fresh.stats_saved_at = old.stats_saved_at;
+    fresh.stabilizing_since = old.stabilizing_since;
+}
+
+// Drops the open edit's staged changes. Stats keep what was counted in the meantime.
+fn rollback_edit(data: &mut AppStateData) {
+    let Some(txn) = data.edit_txn.take() else {
+        return;
+    };
+    let mut restored = *txn.snapshot;
+    carry_runtime_state(data, &mut restored);
+    for service in restored.services.iter_mut() {
+        if let Some(current) = data.services.iter().find(|s| same_service(s, service)) {
+            service.stats = current.stats;
+        }
+    }
+    *data = restored;
+}
+
+fn expire_stale_edit(data: &mut AppStateData) {
+    if let Some(txn) = &data.edit_txn {
+        if now_secs().saturating_sub(txn.last_used) >= EDIT_TXN_TIMEOUT_SECS {
+            warn!(
+                "Rolling back edit transaction {}, unused for too long",
+                txn.id
+            );
+            rollback_edit(data);
+        }
+    }
+}
+
+// In-app edits are refused while an external file is the source of truth
+fn ensure_services_editable(data: &AppStateData) -> Result<(), String> {
+    match &data.services_source_file {
+        Some(path) => Err(format!(
+            "Services are managed by {}. Edit that file instead.",
+            path
+        )),
+        None => Ok(()),
+    }
+}
+
+// Rejects a second service on the same host and port unless duplicates are allowed
+fn ensure_not_duplicate(data: &AppStateData, host: &str, port: &str) -> Result<(), String> {
+    let exists = data
+        .services
+        .iter()
+        .any(|s| bare_host(&s.host).eq_ignore_ascii_case(bare_host(host)) && s.port == port);
+    if exists && !data.allow_duplicates {
+        return Err("Service already exists".to_string());
+    }
+    Ok(())
+}
+
+// Rejects empty names, bad ports and hosts that are neither an IP nor resolvable
+fn validate_service(name: &str, host: &str, port: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Name must not be empty".to_string());
+    }
+    validate_address(host, port)
+}
+
+// Resolves DNS names, so call it before taking the state lock
+fn validate_address(host: &str, port: &str) -> Result<(), String> {
+    match port.parse::<u16>() {
+        Ok(port) if port > 0 => {}
+        _ => return Err(format!("'{}' is not a valid port (1-65535)", port)),
+    }
+    if host.is_empty() {
+        return Err("Host must not be empty".to_string());
+    }
+    if bare_host(host).parse::<IpAddr>().is_err() && resolve(host, "0").is_err() {
+        return Err(format!(
+            "'{}' is not a valid IP address or resolvable hostname",
+            host
+        ));
+    }
+    Ok(())
+}
+
+// A tray menu entry, kept separate from the native menu types so the layout lives in one place
+enum MenuEntry {
+    Item {
+        id: String,
+        text: String,
+        enabled: bool,
+    },
+    // An elided service line; opening it shows the entry with the full name
+    Detail {
+        text: String,
+        entry: Box<MenuEntry>,
+    },
+    Separator,
+}
+
+// Shortens a name to max_len characters (grapheme clusters, so emoji are never split).
+// Limits under 2 leave only the ellipsis.
+fn elide_name(name: &str, max_len: usize) -> String {
+    let graphemes: Vec<&str> = name.graphemes(true).collect();
+    if graphemes.len() <= max_len {
+        return name.to_string();
+    }
+    let keep = max_len.saturating_sub(1);
+    let mut short = graphemes[..keep].concat().trim_end().to_string();
+    short.push('…');
+    short
+}
+
+// Menu names shorter than this couldn't show anything but the ellipsis
+const MIN_MENU_NAME_LEN: usize = 2;
+
+fn validate_menu_name_max_len(max_len: Option<usize>) -> Result<(), String> {
+    match max_len {
+        Some(len) if len < MIN_MENU_NAME_LEN => Err(format!(
+            "Menu name length must be at least {} characters",
+            MIN_MENU_NAME_LEN
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Builds the tray menu layout from the latest check results
+fn menu_model(
+    health_results: &[CheckResult],
+    services: &[Service],
+    name_max_len: Option<usize>,
+    snooze_left: Option<u64>,
+    paused: bool,
+    show_all: bool,
+) -> Vec<MenuEntry> {
+    let mut entries = vec![
+        MenuEntry::Item {
+            id: "show".into(),
+            text: "Manage Services".into(),
+            enabled: true,
+        },
+        MenuEntry::Item {
+            id: if paused { "resume" } else { "pause" }.into(),
+            text: if paused {
+                "Resume Monitoring"
+            } else {
+                "Pause Monitoring"
+            }
+            .into(),
+            enabled: true,
+        },
+        MenuEntry::Item {
+            id: "cycle_icons".into(),
+            text: "Switch Icon Style".into(),
+            enabled: true,
+        },
+    ];
+    if !paused && health_results.iter().any(|r| !r.healthy) {
+        entries.push(MenuEntry::Item {
+            id: "recheck_down".into(),
+            text: "Recheck Down Services".into(),
+            enabled: true,
+        });
+    }
+    if let Some(secs) = snooze_left {
+        entries.push(MenuEntry::Item {
+            id: "snooze".into(),
+            text: format!("🔕 Notifications snoozed ({}m left)", secs.div_ceil(60)),
+            enabled: false,
+        });
+    }
+    entries.push(MenuEntry::Separator);
+
+    // A service line, with the full name one level down when the name had to be elided
+    let service_entry = |icon: &str, name: &str, details: &str, id: String, enabled: bool| {
+        let full = MenuEntry::Item {
+            id,
+            text: format!("{} {}{}", icon, name, details),
+            enabled,
+        };
+        match name_max_len.map(|max_len| elide_name(name, max_len)) {
+            Some(short) if short != name => MenuEntry::Detail {
+                text: format!("{} {}{}", icon, short, details),
+                entry: Box::new(full),
+            },
+            _ => full,
+        }
+    };
+
+    // One line per checked service with its status
+    let status_entry = |result: &CheckResult| {
+        let icon = match (result.state, result.cert_expires_at) {
+            (HealthState::Up, _) => "✅",
+            (HealthState::Degraded, _) | (HealthState::Down, Some(_)) => "⚠",
+            (HealthState::Down, None) => "❌",
+            (HealthState::Maintenance, _) => "🔧",
+        };
+        let mut text = String::new();
+        if let Some(code) = result.status_code {
+            match &result.error {
+                Some(e) => text.push_str(&format!(" ({}, {})", code, e)),
+                None => text.push_str(&format!(" ({})", code)),
+            }
+        } else if let Some(expires_at) = result.cert_expires_at {
+            match cert_days_left(expires_at) {
+                days if days < 0 => text.push_str(" (cert expired)"),
+                days => text.push_str(&format!(" (cert {}d)", days)),
+            }
+        } else if let Some(e) = &result.error {
+            text.push_str(&format!(" ({})", e));
+        }
+        // Clicking an HTTP(S) service opens it in the browser, any other copies its address
+        let action = match result.service.protocol {
+            Protocol::Http | Protocol::Https => "open",
+            _ => "copy",
+        };
+        let index = services
+            .iter()
+            .position(|s| same_service(s, &result.service));
+        if let Some(since) = index.and_then(|i| services[i].down_since) {
+            text.push_str(&format!(
+                " – down {}",
+                format_duration(now_secs().saturating_sub(since))
+            ));
+        }
+        service_entry(
+            icon,
+            &result.service.name,
+            &text,
+            index.map_or("status".into(), |i| format!("{}:{}", action, i)),
+            index.is_some(),
+        )
+    };
+
+    // Unless everything is shown, healthy services collapse into one summary line and only
+    // problems (and pinned services) get their own
+    let (listed, collapsed): (Vec<_>, Vec<_>) = health_results
+        .iter()
+        .partition(|r| show_all || r.service.pinned || r.state != HealthState::Up);
+
+    // Ungrouped services come first, then each group in order of first appearance. Pinned
+    // services lead their section, and each part keeps its relative order.
+    let mut sections: Vec<(Option<&str>, Vec<&CheckResult>)> = vec![(None, Vec::new())];
+    for result in listed {
+        let group = result.service.group.as_deref();
+        match sections.iter_mut().find(|(g, _)| *g == group) {
+            Some((_, results)) => results.push(result),
+            None => sections.push((group, vec![result])),
+        }
+    }
+    for (group, mut results) in sections {
+        results.sort_by_key(|r| !r.service.pinned);
+        if let Some(group) = group {
+            if !matches!(entries.last(), Some(MenuEntry::Separator)) {
+                entries.push(MenuEntry::Separator);
+            }
+            let down = results.iter().filter(|r| !r.healthy).count();
+            let text = match down {
+                0 => format!("— {} —", group),
+                n => format!("— {}: {} down —", group, n),
+            };
+            entries.push(MenuEntry::Item {
+                id: "group".into(),
+                text,
+                enabled: false,
+            });
+        }
+        entries.extend(results.into_iter().map(&status_entry));
+    }
+    if !collapsed.is_empty() {
+        entries.push(MenuEntry::Item {
+            id: "healthy".into(),
+            text: format!("✅ {} healthy", collapsed.len()),
+            enabled: false,
+        });
+    }
+
+    // Disabled services are listed after the checked ones
+    let disabled: Vec<(usize, &Service)> = services
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| !s.enabled)
+        .collect();
+    for (i, service) in &disabled {
+        entries.push(service_entry(
+            "⏸",
+            &service.name,
+            "",
+            format!("copy:{}", i),
+            true,
+        ));
+    }
+
+    if !health_results.is_empty() || !disabled.is_empty() {
+        entries.push(MenuEntry::Separator);
+    }
+    entries.push(MenuEntry::Item {
+        id: "quit".into(),
+        text: "Quit".into(),
+        enabled: true,
+    });
+
+    entries
+}
+
+// The tray menu for the current state
+fn tray_menu_entries(data: &AppStateData) -> Vec<MenuEntry> {
+    menu_model(
+        &data.last_results,
+        &data.services,
+        data.menu_name_max_len,
+        snooze_left(data),
+        data.monitoring_paused,
+        data.tray_show_all,
+    )
+}
+
+// Replaces the tray menu, e.g. after a cycle or a setting that shows in it changed
+fn set_tray_menu(app: &tauri::AppHandle, entries: &[MenuEntry]) {
+    if let Some(tray) = app.tray_by_id("main") {
+        if let Ok(menu) = build_tray_menu(app, entries) {
+            let _ = tray.set_menu(Some(menu));
+        }
+    }
+}
+
+// Shows how many services are down next to the tray icon; platforms without tray text ignore it
+fn set_tray_down_count(app: &tauri::AppHandle, results: &[CheckResult]) {
+    let down = results.iter().filter(|r| !r.healthy).count();
+    set_tray_title_count(app, down);
+}
+
+fn set_tray_title_count(app: &tauri::AppHandle, down: usize) {
+    if let Some(tray) = app.tray_by_id("main") {
+        let title = (down > 0).then(|| down.to_string());
+        let _ = tray.set_title(title);
+    }
+}
+
+// One-line hover summary, e.g. "12 up, 2 down — last checked 14:03:12"
+fn tray_tooltip(results: &[CheckResult], last_check_ts: Option<u64>, paused: bool) -> String {
+    if paused {
+        return "Uptime Watcher — monitoring paused".to_string();
+    }
+    let Some(ts) = last_check_ts else {
+        return "Uptime Watcher — not checked yet".to_string();
+    };
+    let up = results.iter().filter(|r| r.healthy).count();
+    let checked_at = chrono::DateTime::from_timestamp(ts as i64, 0)
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default();
+    format!(
+        "{} up, {} down — last checked {}",
+        up,
+        results.len() - up,
+        checked_at
+    )
+}
+
+// Tooltips aren't supported everywhere (e.g. Linux), where this does nothing
+fn set_tray_tooltip(app: &tauri::AppHandle, tooltip: &str) {
+    if let Some(tray) = app.tray_by_id("main") {
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+// Brings the icon, title, tooltip and menu in line with the state outside of a check cycle
+fn refresh_tray(app: &tauri::AppHandle, data: &AppStateData) {
+    update_tray_icon(app, &data.icon_set, template_mode(data), tray_status(data));
+    if data.monitoring_paused {
+        set_tray_title_count(app, 0);
+    } else {
+        set_tray_down_count(app, &data.last_results);
+    }
+    set_tray_tooltip(
+        app,
+        &tray_tooltip(
+            &data.last_results,
+            data.last_check_ts,
+            data.monitoring_paused,
+        ),
+    );
+    set_tray_menu(app, &tray_menu_entries(data));
+}
+
+// Seconds until notifications resume, if they are snoozed
+fn snooze_left(data: &AppStateData) -> Option<u64> {
+    let now = now_secs();
+    data.snooze_until
+        .filter(|until| *until > now)
+        .map(|until| until - now)
+}
+
+// Renders the menu model as the lines a screen reader or external tool would present
+fn menu_text(data: &AppStateData) -> Vec<String> {
+    let count = |state: HealthState| {
+        data.last_results
+            .iter()
+            .filter(|r| r.state == state)
+            .count()
+    };
+    let (down, degraded) = (count(HealthState::Down), count(HealthState::Degraded));
+    let overall = if data.monitoring_paused {
+        "Monitoring paused".to_string()
+    } else if down > 0 {
+        format!("{} of {} services down", down, data.last_results.len())
+    } else if data.stabilizing_since.is_some() {
+        "Stabilizing after recovery".to_string()
+    } else if degraded > 0 {
+        format!(
+            "{} of {} services degraded",
+            degraded,
+            data.last_results.len()
+        )
+    } else {
+        "All systems normal".to_string()
+    };
+    let checked = match data.last_check_ts {
+        Some(ts) => format!("Last checked {}s ago", now_secs().saturating_sub(ts)),
+        None => "Not checked yet".to_string(),
+    };
+
+    let mut lines = vec![format!("Status: {}", overall), checked];
+    for entry in tray_menu_entries(data) {
+        lines.push(match entry {
+            MenuEntry::Item { text, .. } => text,
+            MenuEntry::Detail { entry, .. } => match *entry {
+                MenuEntry::Item { text, .. } => text,
+                _ => String::new(),
+            },
+            MenuEntry::Separator => String::new(),
+        });
+    }
+    lines
+}
+
+// Turns a menu model into a native menu
+fn build_tray_menu<M: Manager<tauri::Wry>>(
+    manager: &M,
+    entries: &[MenuEntry],
+) -> tauri::Result<Menu<tauri::Wry>> {
+    let items = build_menu_items(manager, entries)?;
+    let item_refs: Vec<&dyn tauri::menu::IsMenuItem<tauri::Wry>> =
+        items.iter().map(|b| b.as_ref()).collect();
+    Menu::with_items(manager, &item_refs)
+}
+
+fn build_menu_items<M: Manager<tauri::Wry>>(
+    manager: &M,
+    entries: &[MenuEntry],
+) -> tauri::Result<Vec<Box<dyn tauri::menu::IsMenuItem<tauri::Wry>>>> {
+    let mut items: Vec<Box<dyn tauri::menu::IsMenuItem<tauri::Wry>>> = Vec::new();
+    for entry in entries {
+        match entry {
+            MenuEntry::Item { id, text, enabled } => {
+                items.push(Box::new(MenuItem::with_id(
+                    manager,
+                    id.clone(),
+                    text,
+                    *enabled,
+                    None::<&str>,
+                )?));
+            }
+            MenuEntry::Detail { text, entry } => {
+                let children = build_menu_items(manager, std::slice::from_ref(&**entry))?;
+                let child_refs: Vec<&dyn tauri::menu::IsMenuItem<tauri::Wry>> =
+                    children.iter().map(|b| b.as_ref()).collect();
+                items.push(Box::new(Submenu::with_items(
+                    manager,
+                    text,
+                    true,
+                    &child_refs,
+                )?));
+            }
+            MenuEntry::Separator => items.push(Box::new(PredefinedMenuItem::separator(manager)?)),
+        }
+    }
+    Ok(items)
+}
+
+// Built-in icon sets, always offered even though their files don't follow the theme naming
+const BUILTIN_ICON_SETS: [&str; 2] = ["default", "alt"];
+
+fn icons_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve("icons", tauri::path::BaseDirectory::Resource)
+        .map_err(|e| format!("Failed to resolve icons directory: {}", e))
+}
+
+// Custom themes are <theme>-healthy.png plus <theme>-unhealthy.png in the icons directory
+fn discover_icon_themes(dir: &Path) -> Vec<String> {
+    let mut themes: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let file_name = e.file_name().to_string_lossy().into_owned();
+                    file_name.strip_suffix("-healthy.png").map(str::to_string)
+                })
+                .filter(|theme| !theme.is_empty())
+                .filter(|theme| dir.join(format!("{}-unhealthy.png", theme)).is_file())
+                .filter(|theme| !BUILTIN_ICON_SETS.contains(&theme.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    themes.sort();
+    themes
+}
+
+// Theme files for Degraded/Stabilizing/Paused are optional and fall back to the healthy icon
+fn theme_icon_path(dir: &Path, theme: &str, status: TrayStatus) -> PathBuf {
+    let file = |suffix: &str| dir.join(format!("{}-{}.png", theme, suffix));
+    let optional = |suffix: &str| {
+        let path = file(suffix);
+        if path.is_file() {
+            path
+        } else {
+            file("healthy")
+        }
+    };
+    match status {
+        TrayStatus::Healthy => file("healthy"),
+        TrayStatus::Degraded => optional("degraded"),
+        TrayStatus::Stabilizing => optional("stabilizing"),
+        TrayStatus::Unhealthy => file("unhealthy"),
+        TrayStatus::Paused => optional("paused"),
+    }
+}
+
+fn icon_path(dir: &Path, icon_set: &str, status: TrayStatus) -> PathBuf {
+    let icon_name = match icon_set {
+        "alt" => match status {
+            TrayStatus::Healthy => "checked.png",
+            TrayStatus::Degraded => "warning.png",
+            TrayStatus::Stabilizing => "pending.png",
+            TrayStatus::Unhealthy => "cross.png",
+            TrayStatus::Paused => "paused.png",
+        },
+        "default" => match status {
+            TrayStatus::Healthy => "green.png",
+            TrayStatus::Degraded | TrayStatus::Stabilizing => "yellow.png",
+            TrayStatus::Unhealthy => "red.png",
+            TrayStatus::Paused => "grey.png",
+        },
+        theme => {
+            if discover_icon_themes(dir).iter().any(|t| t == theme) {
+                return theme_icon_path(dir, theme, status);
+            }
+            // A theme whose files were removed falls back to the default set
+            warn!("Icon set '{}' not found, using default", theme);
+            return icon_path(dir, "default", status);
+        }
+    };
+    dir.join(icon_name)
+}
+
+// Helper to update tray icon
+fn update_tray_icon(app: &tauri::AppHandle, icon_set: &str, template: bool, status: TrayStatus) {
+    if let Ok(resource_path) = icons_dir(app) {
+        let icon_path = icon_path(&resource_path, icon_set, status);
+
+        if let Ok(icon_bytes) = fs::read(&icon_path) {
+            if let Ok(icon) = Image::from_bytes(&icon_bytes) {
+                if let Some(tray) = app.tray_by_id("main") {
+                    // Set icon first, then template status. Some platforms reset status on icon change.
+                    let _ = tray.set_icon(Some(icon));
+                    let _ = tray.set_icon_as_template(template);
+                } else {
+                    error!("Failed to find tray by id 'main'");
+                }
+            } else {
+                error!("Failed to parse icon image");
+            }
+        } else {
+            error!("Failed to read icon file: {:?}", icon_path);
+        }
+    }
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)] // Each field is a separate argument from the frontend
+fn add_service(
+    state: State<AppState>,
+    name: String,
+    ip: String,
+    port: String,
+    protocol: Option<Protocol>,
+    timeout_ms: Option<u64>,
+    description: Option<String>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let (name, ip, port) = (name.trim(), ip.trim(), port.trim());
+    validate_service(name, ip, port)?;
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    ensure_not_duplicate(&data, ip, port)?;
+    data.services.push(Service {
+        name: name.to_string(),
+        host: ip.to_string(),
+        port: port.to_string(),
+        protocol: protocol.unwrap_or_default(),
+        timeout_ms,
+        description: description.unwrap_or_default().trim().to_string(),
+        ..Default::default()
+    });
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+// The add path's per-service validation for a whole list; command services have no host
+fn validate_services(services: &[Service]) -> Result<(), String> {
+    for (i, service) in services.iter().enumerate() {
+        if service.protocol != Protocol::Command {
+            validate_service(
+                service.name.trim(),
+                service.host.trim(),
+                service.port.trim(),
+            )
+            .map_err(|e| format!("Service #{} ('{}'): {}", i + 1, service.name, e))?;
+        }
+    }
+    Ok(())
+}
+
+// Adds many services with a single save. Either all of them are added or, if any is invalid
+// or a duplicate, none are.
+#[tauri::command]
+fn add_services(
+    state: State<AppState>,
+    services: Vec<Service>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    check_service_list(&services)?;
+    validate_services(&services)?;
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let original_len = data.services.len();
+    for (i, service) in services.into_iter().enumerate() {
+        let (host, port) = (service.host.trim(), service.port.trim());
+        // Also catches duplicates within the new list, as accepted ones are already pushed
+        if service.protocol != Protocol::Command {
+            if let Err(e) = ensure_not_duplicate(&data, host, port) {
+                data.services.truncate(original_len);
+                return Err(format!("Service #{} ('{}'): {}", i + 1, service.name, e));
+            }
+        }
+        data.services.push(Service {
+            name: service.name.trim().to_string(),
+            host: host.to_string(),
+            port: port.to_string(),
+            stats: ServiceStats::default(),
+            ..service
+        });
+    }
+    info!("Added {} services", data.services.len() - original_len);
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn set_allow_duplicates(
+    state: State<AppState>,
+    allow: bool,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.allow_duplicates = allow;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_allow_duplicates(state: State<AppState>) -> Result<bool, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.allow_duplicates)
+}
+
+#[tauri::command]
+fn list_check_templates() -> Vec<&'static CheckTemplate> {
+    CHECK_TEMPLATES.iter().collect()
+}
+
+#[tauri::command]
+fn apply_template(
+    state: State<AppState>,
+    name: String,
+    ip: String,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let template = CHECK_TEMPLATES
+        .iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| format!("Unknown template '{}'", name))?;
+    let ip = ip.trim();
+    let service_name = format!("{} ({})", template.name, ip);
+    validate_service(&service_name, ip, &template.port.to_string())?;
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let port = template.port.to_string();
+    ensure_not_duplicate(&data, ip, &port)?;
+    data.services.push(Service {
+        name: service_name,
+        host: ip.to_string(),
+        port,
+        protocol: template.protocol,
+        tcp_exchange: template.exchange.clone(),
+        ..Default::default()
+    });
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn set_service_enabled(
+    state: State<AppState>,
+    index: usize,
+    enabled: bool,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.enabled = enabled;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn set_udp_payload(
+    state: State<AppState>,
+    index: usize,
+    payload: String,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    decode_hex(&payload)?;
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.udp_payload = payload.trim().to_string();
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+// Sets the text a TCP check sends and the reply it expects, or None for a bare connect
+#[tauri::command]
+fn set_tcp_exchange(
+    state: State<AppState>,
+    index: usize,
+    exchange: Option<TcpExchange>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    if let Some(exchange) = &exchange {
+        validate_tcp_exchange(exchange)?;
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.tcp_exchange = exchange;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+fn validate_tcp_exchange(exchange: &TcpExchange) -> Result<(), String> {
+    if exchange.send.is_empty() {
+        return Err("The text to send must not be empty".to_string());
+    }
+    if exchange.expect.is_empty() {
+        return Err("The expected reply must not be empty".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn set_expected_body(
+    state: State<AppState>,
+    index: usize,
+    substring: Option<String>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    // An empty substring would match every body
+    service.expected_body_substring = substring.filter(|s| !s.is_empty());
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+// Turns an HTTP(S) service into a healthz check, or back into a plain one with None
+#[tauri::command]
+fn set_healthz(
+    state: State<AppState>,
+    index: usize,
+    healthz: Option<HealthzCheck>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    if let Some(check) = &healthz {
+        validate_healthz(check)?;
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    if healthz.is_some() && !matches!(service.protocol, Protocol::Http | Protocol::Https) {
+        return Err("Healthz checks need an HTTP or HTTPS service".to_string());
+    }
+    service.healthz = healthz;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+// Sets the step a service must also pass, or removes it with None
+#[tauri::command]
+fn set_secondary_check(
+    state: State<AppState>,
+    index: usize,
+    step: Option<CheckStep>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let step = step.map(|step| CheckStep {
+        name: step.name.trim().to_string(),
+        host: step
+            .host
+            .map(|h| h.trim().to_string())
+            .filter(|h| !h.is_empty()),
+        port: step
+            .port
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty()),
+        ..step
+    });
+    if let Some(step) = &step {
+        validate_check_step(step)?;
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.secondary = step;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn get_secondary_check(state: State<AppState>, index: usize) -> Result<Option<CheckStep>, String> {
+    let data = lock_or_recover(&state.data);
+    let service = data.services.get(index).ok_or("Index out of bounds")?;
+    Ok(service.secondary.clone())
+}
+
+#[tauri::command]
+fn get_healthz(state: State<AppState>, index: usize) -> Result<Option<HealthzCheck>, String> {
+    let data = lock_or_recover(&state.data);
+    let service = data.services.get(index).ok_or("Index out of bounds")?;
+    Ok(service.healthz.clone())
+}
+
+#[tauri::command]
+fn set_degraded_latency_ms(
+    state: State<AppState>,
+    index: usize,
+    latency_ms: Option<u64>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.degraded_latency_ms = latency_ms;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn set_service_interval(
+    state: State<AppState>,
+    index: usize,
+    interval_secs: Option<u64>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    if let Some(secs) = interval_secs {
+        validate_interval(secs)?;
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.interval_secs = interval_secs;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+// Stats of every service, in list order
+#[tauri::command]
+fn get_service_stats(state: State<AppState>) -> Result<Vec<ServiceStats>, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.services.iter().map(|s| s.stats).collect())
+}
+
+// Seconds a service has been down without interruption, None while it is up
+#[tauri::command]
+fn get_downtime(state: State<AppState>, index: usize) -> Result<Option<u64>, String> {
+    let data = lock_or_recover(&state.data);
+    let service = data.services.get(index).ok_or("Index out of bounds")?;
+    Ok(service
+        .down_since
+        .map(|since| now_secs().saturating_sub(since)))
+}
+
+#[tauri::command]
+fn reset_stats(
+    state: State<AppState>,
+    index: usize,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.stats = ServiceStats::default();
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn add_command_service(
+    state: State<AppState>,
+    name: String,
+    command: String,
+    timeout_ms: Option<u64>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let (name, command) = (name.trim(), command.trim());
+    if name.is_empty() {
+        return Err("Name must not be empty".to_string());
+    }
+    if command.is_empty() {
+        return Err("Command must not be empty".to_string());
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    data.services.push(Service {
+        name: name.to_string(),
+        protocol: Protocol::Command,
+        command: command.to_string(),
+        timeout_ms,
+        ..Default::default()
+    });
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn set_service_command(
+    state: State<AppState>,
+    index: usize,
+    command: String,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let command = command.trim();
+    if command.is_empty() {
+        return Err("Command must not be empty".to_string());
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    if service.protocol != Protocol::Command {
+        return Err("Only command services have a command".to_string());
+    }
+    service.command = command.to_string();
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+// Command checks run arbitrary shell commands, so they stay off until explicitly allowed
+#[tauri::command]
+fn set_allow_command_checks(
+    state: State<AppState>,
+    allow: bool,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.allow_command_checks = allow;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_allow_command_checks(state: State<AppState>) -> Result<bool, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.allow_command_checks)
+}
+
+#[tauri::command]
+fn set_service_group(
+    state: State<AppState>,
+    index: usize,
+    group: Option<String>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.group = group
+        .map(|g| g.trim().to_string())
+        .filter(|g| !g.is_empty());
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+// Replaces a service's maintenance windows; an empty list removes them
+#[tauri::command]
+fn set_maintenance_windows(
+    state: State<AppState>,
+    index: usize,
+    windows: Vec<MaintenanceWindow>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    validate_maintenance_windows(&windows)?;
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.maintenance_windows = windows;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+// Replaces a service's quiet hours; None removes them
+#[tauri::command]
+fn set_quiet_hours(
+    state: State<AppState>,
+    index: usize,
+    quiet_hours: Option<QuietHours>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    if let Some(quiet) = &quiet_hours {
+        validate_quiet_hours(quiet)?;
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.quiet_hours = quiet_hours;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn get_quiet_hours(state: State<AppState>, index: usize) -> Result<Option<QuietHours>, String> {
+    let data = lock_or_recover(&state.data);
+    let service = data.services.get(index).ok_or("Index out of bounds")?;
+    Ok(service.quiet_hours.clone())
+}
+
+#[tauri::command]
+fn set_service_critical(
+    state: State<AppState>,
+    index: usize,
+    critical: bool,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.critical = critical;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+// Whether a service's alerts are posted to the webhook
+#[tauri::command]
+fn set_service_webhook(
+    state: State<AppState>,
+    index: usize,
+    enabled: bool,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.webhook = enabled;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn set_service_notify(
+    state: State<AppState>,
+    index: usize,
+    enabled: bool,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.notify = enabled;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+// Splits a service's timeout into connect and read parts; None falls back to timeout_ms
+#[tauri::command]
+fn set_service_timeouts(
+    state: State<AppState>,
+    index: usize,
+    connect_timeout_ms: Option<u64>,
+    read_timeout_ms: Option<u64>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    if connect_timeout_ms == Some(0) || read_timeout_ms == Some(0) {
+        return Err("Timeouts must be at least 1 ms".to_string());
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.connect_timeout_ms = connect_timeout_ms;
+    service.read_timeout_ms = read_timeout_ms;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+// Lets a service's timeout follow its recent latency, or goes back to the static one with None
+#[tauri::command]
+fn set_adaptive_timeout(
+    state: State<AppState>,
+    index: usize,
+    adaptive_timeout: Option<AdaptiveTimeout>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    if let Some(adaptive) = &adaptive_timeout {
+        validate_adaptive_timeout(adaptive)?;
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.adaptive_timeout = adaptive_timeout;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn toggle_pin(
+    state: State<AppState>,
+    index: usize,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let service = data.services.get_mut(index).ok_or("Index out of bounds")?;
+    service.pinned = !service.pinned;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn list_services(state: State<AppState>) -> Result<Vec<Service>, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn get_service(state: State<AppState>, index: usize) -> Result<Service, String> {
+    let data = lock_or_recover(&state.data);
+    data.services
+        .get(index)
+        .cloned()
+        .ok_or_else(|| "Index out of bounds".to_string())
+}
+
+#[tauri::command]
+fn remove_service(
+    state: State<AppState>,
+    index: usize,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    if index < data.services.len() {
+        data.services.remove(index);
+
+        persist(&state, &mut data, txn_id)?;
+
+        Ok(data.services.clone())
+    } else {
+        Err("Index out of bounds".to_string())
+    }
+}
+
+// Removes every service and returns how many there were
+#[tauri::command]
+fn clear_services(state: State<AppState>, txn_id: Option<u64>) -> Result<usize, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let removed = data.services.len();
+    data.services.clear();
+    data.mirrors.clear();
+    data.faults.clear();
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(removed)
+}
+
+#[tauri::command]
+fn move_service(
+    state: State<AppState>,
+    from: usize,
+    to: usize,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    if from >= data.services.len() || to >= data.services.len() {
+        return Err("Index out of bounds".to_string());
+    }
+    let service = data.services.remove(from);
+    data.services.insert(to, service);
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+// Inserts a copy of a service right after it, as a starting point for a similar check.
+// The copy starts without history and shares the host and port, so apart from command
+// checks it needs allow_duplicates.
+#[tauri::command]
+fn duplicate_service(
+    state: State<AppState>,
+    index: usize,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let original = data.services.get(index).ok_or("Index out of bounds")?;
+    if original.protocol != Protocol::Command {
+        ensure_not_duplicate(&data, &original.host, &original.port)?;
+    }
+    let copy = Service {
+        name: format!("{} (copy)", original.name),
+        stats: ServiceStats::default(),
+        last_checked: None,
+        down_since: None,
+        last_notified: None,
+        last_slow_notified: None,
+        ..original.clone()
+    };
+    data.services.insert(index + 1, copy);
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)] // Each field is a separate argument from the frontend
+fn update_service(
+    state: State<AppState>,
+    index: usize,
+    name: String,
+    ip: String,
+    port: String,
+    protocol: Option<Protocol>,
+    timeout_ms: Option<u64>,
+    description: Option<String>,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let (name, ip, port) = (name.trim(), ip.trim(), port.trim());
+    validate_service(name, ip, port)?;
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    if index < data.services.len() {
+        // Only the editable fields change; settings like pinning are kept
+        let service = &mut data.services[index];
+        service.name = name.to_string();
+        service.host = ip.to_string();
+        service.port = port.to_string();
+        if let Some(protocol) = protocol {
+            service.protocol = protocol;
+        }
+        if timeout_ms.is_some() {
+            service.timeout_ms = timeout_ms;
+        }
+        // An empty description clears it, leaving it out keeps the current one
+        if let Some(description) = description {
+            service.description = description.trim().to_string();
+        }
+
+        persist(&state, &mut data, txn_id)?;
+
+        Ok(data.services.clone())
+    } else {
+        Err("Index out of bounds".to_string())
+    }
+}
+
+// Shorter intervals hammer the monitored hosts, longer ones make the tray meaningless
+const MIN_INTERVAL_SECS: u64 = 2;
+const MAX_INTERVAL_SECS: u64 = 86400;
+
+fn validate_interval(secs: u64) -> Result<(), String> {
+    if (MIN_INTERVAL_SECS..=MAX_INTERVAL_SECS).contains(&secs) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Interval must be between {} and {} seconds",
+            MIN_INTERVAL_SECS, MAX_INTERVAL_SECS
+        ))
+    }
+}
+
+#[tauri::command]
+fn set_default_timeout_ms(
+    state: State<AppState>,
+    timeout_ms: u64,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    if timeout_ms == 0 {
+        return Err("Timeout must be at least 1 ms".to_string());
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.default_timeout_ms = timeout_ms;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_default_timeout_ms(state: State<AppState>) -> Result<u64, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.default_timeout_ms)
+}
+
+const MAX_CHECK_JITTER_MS: u64 = 60_000;
+
+#[tauri::command]
+fn set_check_jitter_ms(
+    state: State<AppState>,
+    jitter_ms: u64,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    if jitter_ms > MAX_CHECK_JITTER_MS {
+        return Err(format!("Jitter must be at most {} ms", MAX_CHECK_JITTER_MS));
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.check_jitter_ms = jitter_ms;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_check_jitter_ms(state: State<AppState>) -> Result<u64, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.check_jitter_ms)
+}
+
+#[tauri::command]
+fn set_interval(state: State<AppState>, interval: u64, txn_id: Option<u64>) -> Result<(), String> {
+    validate_interval(interval)?;
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.interval_secs = interval;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+// True when the app started without a settings file, i.e. the user has never configured it
+#[tauri::command]
+fn is_first_run(state: State<AppState>) -> Result<bool, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.first_run)
+}
+
+// Some(backup path) when the settings file could not be read at startup and defaults were used
+#[tauri::command]
+fn get_settings_reset(state: State<AppState>) -> Result<Option<String>, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.settings_backup.clone())
+}
+
+#[tauri::command]
+fn get_interval(state: State<AppState>) -> Result<u64, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.interval_secs)
+}
+
+#[tauri::command]
+fn export_config(state: State<AppState>) -> Result<String, String> {
+    let data = lock_or_recover(&state.data);
+    serde_json::to_string_pretty(&*data).map_err(|e| format!("Failed to serialize config: {}", e))
+}
+
+// The checks both settings.json and an imported config have to pass before they replace the
+// running settings
+fn validate_settings(data: &AppStateData) -> Result<(), String> {
+    check_service_list(&data.services)?;
+    validate_interval(data.interval_secs)?;
+    validate_retry_policy(data.retry_count, data.retry_delay_ms)?;
+    validate_menu_name_max_len(data.menu_name_max_len)
+}
+
+// Replaces services and settings with an exported config. A few settings belong to this
+// machine and are kept: the services source file, agent id, window geometry, any snooze,
+// and allow_command_checks, so an imported config can never turn on running its commands.
+#[tauri::command]
+fn import_config(
+    app: tauri::AppHandle,
+    state: State<AppState>,
+    json: String,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let mut imported: AppStateData =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid config: {}", e))?;
+    validate_settings(&imported).map_err(|e| format!("Invalid config: {}", e))?;
+
+    let (services, autostart) = {
+        let mut data = lock_or_recover(&state.data);
+        check_edit_txn(&mut data, txn_id)?;
+        ensure_services_editable(&data)?;
+        imported.services_source_file = data.services_source_file.clone();
+        imported.agent_id = data.agent_id.clone();
+        imported.window_geometry = data.window_geometry;
+        imported.snooze_until = data.snooze_until;
+        imported.allow_command_checks = data.allow_command_checks;
+        carry_runtime_state(&mut data, &mut imported);
+        // Saved before it replaces anything, so a failed write leaves the running config as
+        // it was, runtime state included
+        if let Err(e) = persist(&state, &mut imported, txn_id) {
+            carry_runtime_state(&mut imported, &mut data);
+            return Err(e);
+        }
+        *data = imported;
+        refresh_tray(&app, &data);
+        (data.services.clone(), data.autostart)
+    };
+
+    // The same follow-up as reloading settings.json, plus the login item
+    configure_api_server(&state)?;
+    apply_autostart(&app, autostart)?;
+
+    Ok(services)
+}
+
+// Re-reads settings.json after it was edited outside the app and replaces the in-memory
+// settings with it. A file that doesn't parse or validate leaves the running state alone.
+#[tauri::command]
+fn reload_settings(app: tauri::AppHandle, state: State<AppState>) -> Result<AppStateData, String> {
+    let path = lock_or_recover(&state.file_path).clone();
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut fresh: AppStateData = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid settings in {}: {}", path.display(), e))?;
+    validate_settings(&fresh).map_err(|e| format!("Invalid settings: {}", e))?;
+
+    let source_changed = {
+        let mut data = lock_or_recover(&state.data);
+        expire_stale_edit(&mut data);
+        // The file holds an open edit's snapshot, so the edit keeps its staged changes on top
+        // of what changed in the file
+        let mut snapshot = None;
+        if let Some(txn) = &data.edit_txn {
+            let staged = with_changes(&data, &settings_json(&txn.snapshot)?, &fresh)?;
+            snapshot = Some(std::mem::replace(&mut fresh, staged));
+        }
+        // Runtime state isn't in the file and carries over
+        carry_runtime_state(&mut data, &mut fresh);
+        if let (Some(txn), Some(snapshot)) = (fresh.edit_txn.as_mut(), snapshot) {
+            *txn.snapshot = snapshot;
+        }
+        let source_changed = fresh.services_source_file != data.services_source_file;
+        *data = fresh;
+        refresh_tray(&app, &data);
+        source_changed
+    };
+    info!("Reloaded settings from {:?}", path);
+
+    // The services source and local API follow the reloaded settings
+    let source_file = lock_or_recover(&state.data).services_source_file.clone();
+    if source_changed {
+        let watcher = match &source_file {
+            Some(source) => Some(watch_services_source(
+                state.data.clone(),
+                PathBuf::from(source),
+            )?),
+            None => None,
+        };
+        let old_watcher = std::mem::replace(&mut *lock_or_recover(&state.source_watcher), watcher);
+        drop(old_watcher);
+    }
+    if let Some(source) = source_file {
+        reload_services_source(&state.data, Path::new(&source));
+    }
+    configure_api_server(&state)?;
+
+    let data = lock_or_recover(&state.data);
+    Ok(data.clone())
+}
+
+#[tauri::command]
+fn set_icon_set(
+    app: tauri::AppHandle,
+    state: State<AppState>,
+    preference: String,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    // println!(
+    //     "Command 'set_icon_set' invoked with preference: {}",
+    //     preference
+    // );
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    if !list_icon_sets(app.clone())?.contains(&preference) {
+        return Err(format!("Unknown icon set: {}", preference));
+    }
+    data.icon_set = preference.clone();
+
+    // Immediate Update using current health state
+    update_tray_icon(&app, &preference, template_mode(&data), tray_status(&data));
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+// Moves to the next available icon set, wrapping around, for the tray's "Switch Icon Style"
+fn cycle_icon_set(app: &tauri::AppHandle) -> Result<(), String> {
+    let sets = list_icon_sets(app.clone())?;
+    let state = app.state::<AppState>();
+    let current = lock_or_recover(&state.data).icon_set.clone();
+    let next = sets
+        .iter()
+        .position(|set| *set == current)
+        .map_or(0, |i| (i + 1) % sets.len());
+    set_icon_set(app.clone(), state, sets[next].clone(), None)
+}
+
+#[tauri::command]
+fn get_icon_set(state: State<AppState>) -> Result<String, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.icon_set.clone())
+}
+
+#[tauri::command]
+fn set_template_mode(
+    app: tauri::AppHandle,
+    state: State<AppState>,
+    enabled: bool,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.template_mode = Some(enabled);
+
+    update_tray_icon(&app, &data.icon_set, enabled, tray_status(&data));
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_template_mode(state: State<AppState>) -> Result<bool, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(template_mode(&data))
+}
+
+// The built-in sets followed by every complete theme in the icons directory
+#[tauri::command]
+fn list_icon_sets(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let dir = icons_dir(&app)?;
+    let mut sets: Vec<String> = BUILTIN_ICON_SETS.iter().map(|s| s.to_string()).collect();
+    sets.extend(discover_icon_themes(&dir));
+    Ok(sets)
+}
+
+#[tauri::command]
+fn set_stabilization_secs(
+    state: State<AppState>,
+    secs: u64,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.stabilization_secs = secs;
+    if secs == 0 {
+        data.stabilizing_since = None;
+    }
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_stabilization_secs(state: State<AppState>) -> Result<u64, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.stabilization_secs)
+}
+
+#[tauri::command]
+fn set_cert_warn_days(
+    state: State<AppState>,
+    days: u64,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.cert_warn_days = days;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_cert_warn_days(state: State<AppState>) -> Result<u64, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.cert_warn_days)
+}
+
+// Retries run inside the check, so a generous policy would hold up the whole cycle
+const MAX_RETRY_COUNT: u32 = 10;
+const MAX_RETRY_DELAY_MS: u64 = 5000;
+
+fn validate_retry_policy(retry_count: u32, retry_delay_ms: u64) -> Result<(), String> {
+    if retry_count > MAX_RETRY_COUNT {
+        return Err(format!("At most {} retries are allowed", MAX_RETRY_COUNT));
+    }
+    if retry_delay_ms > MAX_RETRY_DELAY_MS {
+        return Err(format!(
+            "Retry delay must be at most {} ms",
+            MAX_RETRY_DELAY_MS
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn set_retry_policy(
+    state: State<AppState>,
+    retry_count: u32,
+    retry_delay_ms: u64,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    validate_retry_policy(retry_count, retry_delay_ms)?;
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.retry_count = retry_count;
+    data.retry_delay_ms = retry_delay_ms;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn set_max_concurrency(
+    state: State<AppState>,
+    limit: usize,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    if limit == 0 {
+        return Err("At least one check must be allowed at a time".to_string());
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.max_concurrent_checks = limit;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_max_concurrency(state: State<AppState>) -> Result<usize, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.max_concurrent_checks)
+}
+
+#[tauri::command]
+fn set_down_recheck_secs(
+    state: State<AppState>,
+    secs: u64,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    if secs != 0 {
+        validate_interval(secs)?;
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.down_recheck_secs = secs;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_down_recheck_secs(state: State<AppState>) -> Result<u64, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.down_recheck_secs)
+}
+
+// Where a down service is in the check and alert schedule
+#[derive(Serialize, Debug)]
+struct OutageTiming {
+    down_since: u64,
+    next_check_in_secs: u64,
+    next_notify_in_secs: Option<u64>, // Until its recovery could be alerted on, None = alerts off
+    consecutive_failures: u64,
+}
+
+fn outage_timing(data: &AppStateData, service: &Service, now: u64) -> Option<OutageTiming> {
+    let down_since = service.down_since?;
+    let previous = data
+        .last_results
+        .iter()
+        .find(|r| same_service(&r.service, service));
+    let interval = effective_interval(
+        service,
+        previous,
+        data.interval_secs,
+        data.down_recheck_secs,
+    );
+    let next_check_in_secs = match (data.monitoring_paused, service.last_checked) {
+        (false, Some(last)) => (last + interval).saturating_sub(now),
+        _ => 0,
+    };
+    // The rate limit holds back the next alert, and a snooze all of them
+    let next_notify_in_secs = (data.notifications_enabled && service.notify).then(|| {
+        let limit = service.last_notified.map_or(0, |(at, _)| {
+            (at + data.notification_min_interval_secs).saturating_sub(now)
+        });
+        limit.max(snooze_left(data).unwrap_or(0))
+    });
+    Some(OutageTiming {
+        down_since,
+        next_check_in_secs,
+        next_notify_in_secs,
+        consecutive_failures: service.stats.current_streak.min(0).unsigned_abs(),
+    })
+}
+
+// The outage timing of a service, or None while it is up
+#[tauri::command]
+fn get_outage_timing(state: State<AppState>, index: usize) -> Result<Option<OutageTiming>, String> {
+    let data = lock_or_recover(&state.data);
+    let service = data.services.get(index).ok_or("Index out of bounds")?;
+    Ok(outage_timing(&data, service, now_secs()))
+}
+
+#[derive(Serialize)]
+struct RetryPolicy {
+    retry_count: u32,
+    retry_delay_ms: u64,
+}
+
+#[tauri::command]
+fn get_retry_policy(state: State<AppState>) -> Result<RetryPolicy, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(RetryPolicy {
+        retry_count: data.retry_count,
+        retry_delay_ms: data.retry_delay_ms,
+    })
+}
+
+#[tauri::command]
+fn set_verbose_logging(
+    state: State<AppState>,
+    enabled: bool,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.verbose_logging = enabled;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_verbose_logging(state: State<AppState>) -> Result<bool, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.verbose_logging)
+}
+
+#[tauri::command]
+fn set_health_policy(
+    state: State<AppState>,
+    policy: HealthPolicy,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.health_policy = policy;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_health_policy(state: State<AppState>) -> Result<HealthPolicy, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.health_policy)
+}
+
+#[tauri::command]
+fn set_structured_event_output(
+    state: State<AppState>,
+    output: EventOutput,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.structured_event_output = output;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_structured_event_output(state: State<AppState>) -> Result<EventOutput, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.structured_event_output)
+}
+
+#[tauri::command]
+fn get_last_latencies(state: State<AppState>) -> Result<Vec<CheckResult>, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.last_results.clone())
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct LatencyPercentiles {
+    name: String,
+    p50: Option<u64>,
+    p90: Option<u64>,
+    p99: Option<u64>,
+    samples: usize,
+}
+
+fn latency_percentiles(name: &str, window: Option<&VecDeque<u64>>) -> LatencyPercentiles {
+    let mut sorted: Vec<u64> = window.into_iter().flatten().copied().collect();
+    sorted.sort_unstable();
+    LatencyPercentiles {
+        name: name.to_string(),
+        p50: percentile(&sorted, 50.0),
+        p90: percentile(&sorted, 90.0),
+        p99: percentile(&sorted, 99.0),
+        samples: sorted.len(),
+    }
+}
+
+// Latency percentiles over each service's last LATENCY_WINDOW successful checks this run
+#[tauri::command]
+fn get_latency_percentiles(state: State<AppState>) -> Result<Vec<LatencyPercentiles>, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data
+        .services
+        .iter()
+        .map(|service| {
+            latency_percentiles(
+                &service.name,
+                data.latency_samples.get(&service_key(service)),
+            )
+        })
+        .collect())
+}
+
+// Appends an unsigned LEB128 varint
+fn push_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+const PACKED_STATUS_VERSION: u8 = 1;
+
+// The latest results as (healthy, latency ms) in the compact format described in the README
+fn pack_status(revision: u64, timestamp: u64, results: &[(bool, Option<u64>)]) -> Vec<u8> {
+    let mut out = vec![PACKED_STATUS_VERSION];
+    push_varint(&mut out, revision);
+    push_varint(&mut out, timestamp);
+    push_varint(&mut out, results.len() as u64);
+    for chunk in results.chunks(8) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u8, |bits, (i, (healthy, _))| {
+                bits | ((*healthy as u8) << i)
+            });
+        out.push(bits);
+    }
+    for (_, latency) in results {
+        push_varint(&mut out, latency.map_or(0, |ms| ms + 1));
+    }
+    out
+}
+
+// A cheaper alternative to get_last_latencies for frequent polling, same results and order
+#[tauri::command]
+fn get_status_packed(state: State<AppState>) -> Result<Vec<u8>, String> {
+    let data = lock_or_recover(&state.data);
+    let results: Vec<(bool, Option<u64>)> = data
+        .last_results
+        .iter()
+        .map(|r| (r.healthy, r.latency_ms.map(|ms| ms as u64)))
+        .collect();
+    Ok(pack_status(
+        data.cycle_revision,
+        data.last_check_ts.unwrap_or(0),
+        &results,
+    ))
+}
+
+#[tauri::command]
+fn get_uptime_stats(
+    state: State<AppState>,
+    window_secs: u64,
+) -> Result<Vec<(String, f64)>, String> {
+    let path = lock_or_recover(&state.file_path);
+    let cutoff = now_secs().saturating_sub(window_secs);
+
+    // (name, up, total) in order of first appearance
+    let mut counts: Vec<(String, u64, u64)> = Vec::new();
+    for entry in read_history(&history_path(&path)) {
+        if entry.ts < cutoff {
+            continue;
+        }
+        let index = match counts.iter().position(|(name, _, _)| *name == entry.name) {
+            Some(index) => index,
+            None => {
+                counts.push((entry.name, 0, 0));
+                counts.len() - 1
+            }
+        };
+        counts[index].1 += entry.healthy as u64;
+        counts[index].2 += 1;
+    }
+
+    Ok(counts
+        .into_iter()
+        .map(|(name, up, total)| (name, up as f64 * 100.0 / total as f64))
+        .collect())
+}
+
+#[tauri::command]
+fn set_notifications_enabled(
+    state: State<AppState>,
+    enabled: bool,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.notifications_enabled = enabled;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+// Mutes notifications, the alert sound and webhooks for the given number of minutes; 0 ends
+// a snooze early
+#[tauri::command]
+fn snooze_notifications(
+    app: tauri::AppHandle,
+    state: State<AppState>,
+    minutes: u64,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.snooze_until = (minutes > 0).then(|| now_secs() + minutes * 60);
+    set_tray_menu(&app, &tray_menu_entries(&data));
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn set_notification_min_interval_secs(
+    state: State<AppState>,
+    secs: u64,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.notification_min_interval_secs = secs;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_notification_min_interval_secs(state: State<AppState>) -> Result<u64, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.notification_min_interval_secs)
+}
+
+// Stops or restarts every check, leaving the last results in the menu while paused
+fn set_monitoring_paused(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    paused: bool,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.monitoring_paused = paused;
+    info!("Monitoring {}", if paused { "paused" } else { "resumed" });
+    refresh_tray(app, &data);
+
+    persist(state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn pause_monitoring(
+    app: tauri::AppHandle,
+    state: State<AppState>,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    set_monitoring_paused(&app, &state, true, txn_id)
+}
+
+#[tauri::command]
+fn resume_monitoring(
+    app: tauri::AppHandle,
+    state: State<AppState>,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    set_monitoring_paused(&app, &state, false, txn_id)
+}
+
+#[tauri::command]
+fn is_monitoring_paused(state: State<AppState>) -> Result<bool, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.monitoring_paused)
+}
+
+#[tauri::command]
+fn set_sound_enabled(
+    state: State<AppState>,
+    enabled: bool,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.sound_enabled = enabled;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_sound_enabled(state: State<AppState>) -> Result<bool, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.sound_enabled)
+}
+
+#[tauri::command]
+fn get_notifications_enabled(state: State<AppState>) -> Result<bool, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.notifications_enabled)
+}
+
+#[tauri::command]
+fn get_menu_text(state: State<AppState>) -> Result<Vec<String>, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(menu_text(&data))
+}
+
+// The aggregate health behind the tray icon, None until the first cycle has finished and
+// while monitoring is paused. Stabilizing after a recovery shows amber in the tray, so it
+// reads as degraded here.
+#[tauri::command]
+fn get_overall_health(state: State<AppState>) -> Result<Option<HealthState>, String> {
+    let data = lock_or_recover(&state.data);
+    if data.last_check_ts.is_none() {
+        return Ok(None);
+    }
+    Ok(match tray_status(&data) {
+        TrayStatus::Healthy => Some(HealthState::Up),
+        TrayStatus::Degraded | TrayStatus::Stabilizing => Some(HealthState::Degraded),
+        TrayStatus::Unhealthy => Some(HealthState::Down),
+        TrayStatus::Paused => None,
+    })
+}
+
+// Counts for a dashboard header, all taken under one lock so they agree with each other
+#[derive(Serialize)]
+struct Summary {
+    total: usize,
+    up: usize,   // Up, degraded or in maintenance
+    down: usize, // Checked and failing
+    disabled: usize,
+    overall_healthy: bool, // The tray's view, after the health policy
+    last_cycle_ts: Option<u64>,
+}
+
+#[tauri::command]
+fn get_summary(state: State<AppState>) -> Result<Summary, String> {
+    let data = lock_or_recover(&state.data);
+    let up = data.last_results.iter().filter(|r| r.healthy).count();
+    Ok(Summary {
+        total: data.services.len(),
+        up,
+        down: data.last_results.len() - up,
+        disabled: data.services.iter().filter(|s| !s.enabled).count(),
+        overall_healthy: data.is_healthy,
+        last_cycle_ts: data.last_check_ts,
+    })
+}
+
+#[tauri::command]
+fn begin_edit(state: State<AppState>) -> Result<u64, String> {
+    let mut data = lock_or_recover(&state.data);
+    expire_stale_edit(&mut data);
+    if data.edit_txn.is_some() {
+        return Err("Another edit transaction is already open".to_string());
+    }
+    data.last_txn_id += 1;
+    let snapshot = Box::new(data.clone());
+    data.edit_txn = Some(EditTxn {
+        id: data.last_txn_id,
+        last_used: now_secs(),
+        snapshot,
+        untagged_base: None,
+    });
+    Ok(data.last_txn_id)
+}
+
+#[tauri::command]
+fn commit_edit(state: State<AppState>, txn_id: u64) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, Some(txn_id))?;
+
+    // Save, keeping the transaction open if that fails so the commit can be retried
+    let path = lock_or_recover(&state.file_path);
+    save_state(&data, &path)?;
+    data.edit_txn = None;
+
+    Ok(())
+}
+
+// Discards every change made in the transaction, leaving the settings as they were at begin_edit
+#[tauri::command]
+fn abort_edit(app: tauri::AppHandle, state: State<AppState>, txn_id: u64) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, Some(txn_id))?;
+    rollback_edit(&mut data);
+    refresh_tray(&app, &data);
+    Ok(())
+}
+
+#[tauri::command]
+fn set_menu_name_max_len(
+    state: State<AppState>,
+    max_len: Option<usize>,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    validate_menu_name_max_len(max_len)?;
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.menu_name_max_len = max_len;
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_menu_name_max_len(state: State<AppState>) -> Result<Option<usize>, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.menu_name_max_len)
+}
+
+#[tauri::command]
+fn set_tray_show_all(
+    app: tauri::AppHandle,
+    state: State<AppState>,
+    show_all: bool,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.tray_show_all = show_all;
+    set_tray_menu(&app, &tray_menu_entries(&data));
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_tray_show_all(state: State<AppState>) -> Result<bool, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.tray_show_all)
+}
+
+#[tauri::command]
+fn set_services_source_file(
+    state: State<AppState>,
+    path: Option<String>,
+) -> Result<Vec<Service>, String> {
+    // Validate before switching so a bad path never replaces the current list
+    let (services, new_watcher) = match &path {
+        Some(path) => {
+            let source = PathBuf::from(path);
+            let services = read_services_file(&source)?;
+            let watcher = watch_services_source(state.data.clone(), source)?;
+            (Some(services), Some(watcher))
+        }
+        None => (None, None),
+    };
+
+    // Swap watchers before taking the data lock, since a dropped watcher may wait on its callback
+    let old_watcher = {
+        let mut watcher = lock_or_recover(&state.source_watcher);
+        std::mem::replace(&mut *watcher, new_watcher)
+    };
+    drop(old_watcher);
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, None)?;
+    // Clearing the source keeps the last loaded services and hands management back to the app
+    if let Some(services) = services {
+        data.services = services;
+    }
+    data.services_source_file = path;
+    data.services_source_error = None;
+
+    persist(&state, &mut data, None)?;
+
+    Ok(data.services.clone())
+}
+
+// One-off import of a JSON services file. With `replace` the file becomes the whole list;
+// otherwise entries matching an existing service update it and the rest are appended.
+// set_services_source_file is the watched alternative.
+#[tauri::command]
+fn load_services_from_file(
+    state: State<AppState>,
+    path: String,
+    replace: bool,
+    txn_id: Option<u64>,
+) -> Result<Vec<Service>, String> {
+    let services = read_services_file(Path::new(path.trim()))?;
+    validate_services(&services)?;
+
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    ensure_services_editable(&data)?;
+    let original = data.services.clone();
+    if replace {
+        data.services.clear();
+    }
+    for (i, service) in services.into_iter().enumerate() {
+        // Known services keep their stats and outage timer
+        if let Some(old) = original.iter().find(|s| same_service(s, &service)) {
+            let service = Service {
+                stats: old.stats,
+                down_since: old.down_since,
+                ..service
+            };
+            match data.services.iter_mut().find(|s| same_service(s, &service)) {
+                Some(existing) => *existing = service,
+                None => data.services.push(service),
+            }
+            continue;
+        }
+        if service.protocol != Protocol::Command {
+            if let Err(e) = ensure_not_duplicate(&data, &service.host, &service.port) {
+                data.services = original;
+                return Err(format!("Service #{} ('{}'): {}", i + 1, service.name, e));
+            }
+        }
+        data.services.push(Service {
+            stats: ServiceStats::default(),
+            ..service
+        });
+    }
+    info!("Loaded {} services from {}", data.services.len(), path);
+
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(data.services.clone())
+}
+
+#[tauri::command]
+fn get_services_source(state: State<AppState>) -> Result<ServicesSource, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(ServicesSource {
+        path: data.services_source_file.clone(),
+        error: data.services_source_error.clone(),
+    })
+}
+
+#[tauri::command]
+fn mirror_service(
+    state: State<AppState>,
+    index: usize,
+    new_ip: String,
+    new_port: String,
+) -> Result<(), String> {
+    let (new_ip, new_port) = (new_ip.trim(), new_port.trim());
+    validate_address(new_ip, new_port)?;
+
+    let mut data = lock_or_recover(&state.data);
+    let service = data.services.get(index).ok_or("Index out of bounds")?;
+    let mirror = Mirror {
+        old_ip: service.host.clone(),
+        old_port: service.port.clone(),
+        new_ip: new_ip.to_string(),
+        new_port: new_port.to_string(),
+        samples: VecDeque::new(),
+    };
+    let key = service_key(service);
+    data.mirrors.insert(key, mirror);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_mirror_comparison(
+    state: State<AppState>,
+    index: usize,
+) -> Result<Vec<MirrorSample>, String> {
+    let data = lock_or_recover(&state.data);
+    let mirror = data
+        .mirrors
+        .get(&service_key_at(&data, index)?)
+        .ok_or("Service is not being mirrored")?;
+    Ok(mirror.samples.iter().cloned().collect())
+}
+
+#[tauri::command]
+fn stop_mirror(state: State<AppState>, index: usize) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    let key = service_key_at(&data, index)?;
+    data.mirrors
+        .remove(&key)
+        .map(|_| ())
+        .ok_or_else(|| "Service is not being mirrored".to_string())
+}
+
+#[tauri::command]
+fn set_fault_injection(
+    state: State<AppState>,
+    index: usize,
+    added_latency_ms: u64,
+    loss_pct: f64,
+) -> Result<(), String> {
+    if !(0.0..=100.0).contains(&loss_pct) {
+        return Err("Loss percentage must be between 0 and 100".to_string());
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    let key = service_key_at(&data, index)?;
+    // Never saved: injected faults must not outlive the session
+    data.faults.insert(
+        key,
+        FaultInjection {
+            added_latency_ms,
+            loss_pct,
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn clear_fault_injection(state: State<AppState>, index: usize) -> Result<(), String> {
+    let mut data = lock_or_recover(&state.data);
+    let key = service_key_at(&data, index)?;
+    data.faults
+        .remove(&key)
+        .map(|_| ())
+        .ok_or_else(|| "No fault injection set for this service".to_string())
+}
+
+// Longest an escalation can be set for
+const MAX_ESCALATION_SECS: u64 = 86400;
+
+// Turns on extra alert channels ("desktop", "sound") for a service for duration_secs, e.g. one
+// that normally has notify off. Escalations are runtime only and end on restart; snooze,
+// quiet hours and the rate limit still apply. A duration of 0 ends an escalation early.
+// Webhooks already fire for every service and aren't a channel.
+#[tauri::command]
+fn escalate_service(
+    state: State<AppState>,
+    index: usize,
+    channels: Vec<String>,
+    duration_secs: u64,
+) -> Result<(), String> {
+    let channels = channels
+        .iter()
+        .map(
+            |channel| match channel.trim().to_ascii_lowercase().as_str() {
+                "desktop" => Ok(AlertChannel::Desktop),
+                "sound" => Ok(AlertChannel::Sound),
+                "webhook" => Ok(AlertChannel::Webhook),
+                // There is no SMTP client or mail server setting to send it with
+                "email" => Err("Email alerts are not supported, use a webhook".to_string()),
+                other => Err(format!(
+                    "Unknown alert channel '{}', expected desktop, sound or webhook",
+                    other
+                )),
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+    if duration_secs > MAX_ESCALATION_SECS {
+        return Err(format!(
+            "Escalations last at most {} seconds",
+            MAX_ESCALATION_SECS
+        ));
+    }
+
+    let mut data = lock_or_recover(&state.data);
+    let key = service_key_at(&data, index)?;
+    if duration_secs == 0 || channels.is_empty() {
+        data.escalations.remove(&key);
+        return Ok(());
+    }
+    // An escalation adds a service to a channel, it can't turn on one that is off for all
+    for channel in &channels {
+        match channel {
+            AlertChannel::Desktop if !data.notifications_enabled => {
+                return Err("Desktop notifications are turned off".to_string())
+            }
+            AlertChannel::Sound if !data.sound_enabled => {
+                return Err("The alert sound is turned off".to_string())
+            }
+            AlertChannel::Webhook if data.webhook_url.is_none() => {
+                return Err("No webhook URL is set".to_string())
+            }
+            _ => {}
+        }
+    }
+    let until = now_secs() + duration_secs;
+    data.escalations.insert(key, Escalation { channels, until });
+    Ok(())
+}
+
+#[derive(Serialize, Debug)]
+struct EscalationInfo {
+    index: usize,
+    name: String,
+    channels: Vec<AlertChannel>,
+    remaining_secs: u64,
+}
+
+fn escalation_info(
+    data: &AppStateData,
+    index: usize,
+    service: &Service,
+    now: u64,
+) -> Option<EscalationInfo> {
+    let escalation = data.escalations.get(&service_key(service))?;
+    (escalation.until > now).then(|| EscalationInfo {
+        index,
+        name: service.name.clone(),
+        channels: escalation.channels.clone(),
+        remaining_secs: escalation.until - now,
+    })
+}
+
+// The escalations still running, in service order
+#[tauri::command]
+fn get_escalations(state: State<AppState>) -> Result<Vec<EscalationInfo>, String> {
+    let data = lock_or_recover(&state.data);
+    let now = now_secs();
+    Ok(data
+        .services
+        .iter()
+        .enumerate()
+        .filter_map(|(index, service)| escalation_info(&data, index, service, now))
+        .collect())
+}
+
+#[derive(Serialize, Debug)]
+struct ServiceDetail {
+    service: Service,
+    result: Option<CheckResult>, // From the latest cycle that checked it
+    outage: Option<OutageTiming>,
+    escalation: Option<EscalationInfo>,
+}
+
+// Everything known about one service: its settings, latest result, outage and escalation
+#[tauri::command]
+fn get_service_detail(state: State<AppState>, index: usize) -> Result<ServiceDetail, String> {
+    let data = lock_or_recover(&state.data);
+    let service = data.services.get(index).ok_or("Index out of bounds")?;
+    let now = now_secs();
+    Ok(ServiceDetail {
+        service: service.clone(),
+        result: data
+            .last_results
+            .iter()
+            .find(|r| same_service(&r.service, service))
+            .cloned(),
+        outage: outage_timing(&data, service, now),
+        escalation: escalation_info(&data, index, service, now),
+    })
+}
+
+#[tauri::command]
+fn get_fd_usage() -> FdUsage {
+    fd_usage()
+}
+
+fn fd_usage() -> FdUsage {
+    // Linux exposes open descriptors under /proc, macOS under /dev/fd
+    let open = ["/proc/self/fd", "/dev/fd"]
+        .iter()
+        .find_map(|dir| fs::read_dir(dir).ok())
+        .map(|entries| entries.count());
+    FdUsage {
+        open,
+        soft_limit: fd_soft_limit(),
+    }
+}
+
+#[cfg(unix)]
+fn fd_soft_limit() -> Option<u64> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit only writes into the struct we pass it
+    let ok = unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } == 0;
+    ok.then_some(limit.rlim_cur)
+}
+
+#[cfg(not(unix))]
+fn fd_soft_limit() -> Option<u64> {
+    None
+}
+
+// Raises the soft descriptor limit as far as the hard limit allows (macOS defaults to 256)
+#[cfg(unix)]
+fn raise_fd_limit() {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit/setrlimit only read and write the struct we pass them
+    unsafe {
+        if libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) != 0 {
+            return;
+        }
+        // macOS rejects anything above OPEN_MAX even when the hard limit is unlimited
+        let target = if cfg!(target_os = "macos") {
+            limit.rlim_max.min(10240)
+        } else {
+            limit.rlim_max
+        };
+        if target > limit.rlim_cur {
+            let previous = limit.rlim_cur;
+            limit.rlim_cur = target;
+            if libc::setrlimit(libc::RLIMIT_NOFILE, &limit) == 0 {
+                info!("Raised open file limit from {} to {}", previous, target);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn raise_fd_limit() {}
+
+fn warn_on_fd_pressure() {
+    if let FdUsage {
+        open: Some(open),
+        soft_limit: Some(limit),
+    } = fd_usage()
+    {
+        if limit > 0 && open as f64 >= limit as f64 * FD_WARN_RATIO {
+            warn!(
+                "⚠️ {} of {} file descriptors in use, checks may start failing",
+                open, limit
+            );
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Hosts may be written with or without brackets around an IPv6 address
+fn bare_host(host: &str) -> &str {
+    host.strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host)
+}
+
+// Joins host and port, bracketing IPv6 addresses ("[::1]:80") as socket addresses and URLs need
+fn host_port(host: &str, port: &str) -> String {
+    let host = bare_host(host);
+    if host.parse::<Ipv6Addr>().is_ok() {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+// Resolves host:port (an IP address or DNS name) to every address it points at
+fn resolve(host: &str, port: &str) -> Result<Vec<SocketAddr>, String> {
+    let addrs: Vec<SocketAddr> = host_port(host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve '{}': {}", host, e))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("'{}' did not resolve to any address", host));
+    }
+    Ok(addrs)
+}
+
+// Tries each resolved address in turn until one accepts the connection
+fn tcp_connect(host: &str, port: &str, timeout: Duration) -> Result<bool, String> {
+    let addrs = resolve(host, port)?;
+    Ok(connect_any(&addrs, timeout).is_ok())
+}
+
+// Only the start of a TCP reply is searched for the expected text
+const MAX_REPLY_BYTES: usize = 4096;
+
+// Connects, sends the exchange's text and reads until the reply contains the expected text
+fn tcp_exchange_check(
+    service: &Service,
+    exchange: &TcpExchange,
+    timeout: Duration,
+) -> Result<bool, String> {
+    let (connect_timeout, read_timeout) = split_timeouts(service, timeout);
+    let addrs = resolve(&service.host, &service.port)?;
+    let Some(mut stream) = addrs
+        .iter()
+        .find_map(|addr| TcpStream::connect_timeout(addr, connect_timeout).ok())
+    else {
+        return Ok(false);
+    };
+    let _ = stream.set_read_timeout(Some(read_timeout));
+    let _ = stream.set_write_timeout(Some(read_timeout));
+    stream
+        .write_all(exchange.send.as_bytes())
+        .map_err(|e| format!("Send failed: {}", e))?;
+
+    // The reply can arrive in pieces
+    let mut reply = Vec::new();
+    let mut buf = [0u8; 512];
+    while reply.len() < MAX_REPLY_BYTES {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => reply.extend_from_slice(&buf[..n]),
+            Err(e) if reply.is_empty() => {
+                return Err(match e.kind() {
+                    ErrorKind::WouldBlock | ErrorKind::TimedOut => "Reply timed out".to_string(),
+                    _ => format!("Reply read failed: {}", e),
+                })
+            }
+            Err(_) => break,
+        }
+        if String::from_utf8_lossy(&reply).contains(&*exchange.expect) {
+            return Ok(true);
+        }
+    }
+    Err("Unexpected reply".to_string())
+}
+
+// Tries each address in turn, returning the last connection error if none accepted
+fn connect_any(addrs: &[SocketAddr], timeout: Duration) -> Result<(), String> {
+    let mut last_error = "No addresses to connect to".to_string();
+    for addr in addrs {
+        match TcpStream::connect_timeout(addr, timeout) {
+            Ok(_) => return Ok(()),
+            Err(e) => last_error = format!("{}: {}", addr, e),
+        }
+    }
+    Err(last_error)
+}
+
+// Checks both endpoints of each mirror the way the service itself is checked, with its
+// protocol, timeout and expectations, and records the results side by side
+fn check_mirrors(
+    data: &Mutex<AppStateData>,
+    timeouts: &HashMap<ServiceKey, u64>,
+    settings: CheckSettings,
+) {
+    let targets: Vec<(ServiceKey, Service, Mirror)> = {
+        let data = lock_or_recover(data);
+        data.services
+            .iter()
+            .filter_map(|service| {
+                let key = service_key(service);
+                let mirror = data.mirrors.get(&key)?.clone();
+                Some((key, service.clone(), mirror))
+            })
+            .collect()
+    };
+
+    for (key, service, mirror) in targets {
+        let timeout_ms = timeouts.get(&key).copied();
+        let endpoint = |host: &str, port: &str| Service {
+            host: host.to_string(),
+            port: port.to_string(),
+            ..service.clone()
+        };
+        let old = check_service(
+            &endpoint(&mirror.old_ip, &mirror.old_port),
+            None,
+            timeout_ms,
+            settings,
+        );
+        let new = check_service(
+            &endpoint(&mirror.new_ip, &mirror.new_port),
+            None,
+            timeout_ms,
+            settings,
+        );
+        let sample = MirrorSample {
+            timestamp: now_secs(),
+            old_healthy: old.healthy,
+            new_healthy: new.healthy,
+            old_latency_ms: old.latency_ms,
+            new_latency_ms: new.latency_ms,
+        };
+
+        let mut data = lock_or_recover(data);
+        // The mirror may have been stopped while we were checking
+        if let Some(mirror) = data.mirrors.get_mut(&key) {
+            if mirror.samples.len() >= MIRROR_HISTORY {
+                mirror.samples.pop_front();
+            }
+            mirror.samples.push_back(sample);
+        }
+    }
+}
+
+fn write_structured_event(output: EventOutput, events_path: &Path, event: &StructuredEvent) {
+    let line = match serde_json::to_string(event) {
+        Ok(line) => line,
+        Err(e) => {
+            error!("Failed to serialize event: {}", e);
+            return;
+        }
+    };
+    match output {
+        EventOutput::Off => {}
+        EventOutput::Stdout => println!("{}", line),
+        EventOutput::File => {
+            let written = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(events_path)
+                .and_then(|mut file| writeln!(file, "{}", line));
+            if let Err(e) = written {
+                error!("Failed to write event to {:?}: {}", events_path, e);
+            }
+        }
+    }
+}
+
+// Whether two entries describe the same service, so edits to it don't read as transitions
+fn same_service(a: &Service, b: &Service) -> bool {
+    a.name == b.name && a.host == b.host && a.port == b.port
+}
+
+// The latest earlier health of the service checked in `current`, if it was checked before
+fn previous_health(previous: &[CheckResult], current: &CheckResult) -> Option<bool> {
+    previous
+        .iter()
+        .find(|prev| same_service(&prev.service, &current.service))
+        .map(|prev| prev.healthy)
+}
+
+// Whether a successful check took longer than the service's degraded_latency_ms
+fn too_slow(service: &Service, latency_ms: Option<u128>) -> bool {
+    match (latency_ms, service.degraded_latency_ms) {
+        (Some(ms), Some(max)) => ms > max as u128,
+        _ => false,
+    }
+}
+
+// Services that were up at normal speed and have just crossed their latency threshold.
+// Staying slow doesn't count again, and going down is a health transition instead.
+fn slow_transitions<'a>(
+    previous: &[CheckResult],
+    current: &'a [CheckResult],
+) -> Vec<&'a CheckResult> {
+    current
+        .iter()
+        .filter(|result| !result.injected)
+        .filter(|result| result.healthy && too_slow(&result.service, result.latency_ms))
+        .filter(|result| {
+            previous
+                .iter()
+                .find(|prev| same_service(&prev.service, &result.service))
+                .is_some_and(|prev| {
+                    !prev.injected && prev.healthy && !too_slow(&result.service, prev.latency_ms)
+                })
+        })
+        .collect()
+}
+
+// Services whose health flipped since they were last checked. A fault-injected result on
+// either side is test data and never counts as a change to alert on.
+fn transitions<'a>(previous: &[CheckResult], current: &'a [CheckResult]) -> Vec<&'a CheckResult> {
+    current
+        .iter()
+        .filter(|result| !result.injected)
+        .filter(|result| {
+            previous
+                .iter()
+                .find(|prev| same_service(&prev.service, &result.service))
+                .is_some_and(|prev| !prev.injected && prev.healthy != result.healthy)
+        })
+        .collect()
+}
+
+// Picks the results to alert on, at most one per service every `min_interval` seconds.
+// Transitions inside that window are coalesced: once it has passed, a service whose health
+// differs from what it was last alerted as gets one alert for where it ended up.
+fn rate_limited_alerts<'a>(
+    services: &mut [Service],
+    results: &'a [CheckResult],
+    changed: &[&CheckResult],
+    min_interval: u64,
+    now: u64,
+) -> Vec<&'a CheckResult> {
+    let mut alerts = Vec::new();
+    for result in results {
+        if result.injected {
+            continue;
+        }
+        let Some(service) = services
+            .iter_mut()
+            .find(|s| same_service(s, &result.service))
+        else {
+            continue;
+        };
+        let transitioned = changed.iter().any(|c| std::ptr::eq(*c, result));
+        // Quiet hours hold the alert back. The change is remembered as still to be alerted
+        // on, so a service that hasn't flipped back by the time they end is alerted on then.
+        if quiet_now(service) {
+            if transitioned && service.last_notified.is_none() {
+                service.last_notified = Some((0, !result.healthy));
+            }
+            continue;
+        }
+        let alert = match service.last_notified {
+            None => transitioned,
+            Some((at, healthy)) => {
+                healthy != result.healthy && now.saturating_sub(at) >= min_interval
+            }
+        };
+        if alert {
+            service.last_notified = Some((now, result.healthy));
+            alerts.push(result);
+        } else if transitioned {
+            info!(
+                "Not alerting on {} yet, it was alerted on in the last {}s",
+                result.service.name, min_interval
+            );
+        }
+    }
+    alerts
+}
+
+// The slow services that weren't already alerted on as slow in the last min_interval seconds
+fn rate_limited_slow<'a>(
+    services: &mut [Service],
+    slow: &[&'a CheckResult],
+    min_interval: u64,
+    now: u64,
+) -> Vec<&'a CheckResult> {
+    let mut alerts = Vec::new();
+    for result in slow.iter().filter(|r| !quiet_now(&r.service)) {
+        let Some(service) = services
+            .iter_mut()
+            .find(|s| same_service(s, &result.service))
+        else {
+            continue;
+        };
+        if service
+            .last_slow_notified
+            .is_some_and(|at| now.saturating_sub(at) < min_interval)
+        {
+            info!(
+                "Not alerting on {} being slow yet, it was alerted on in the last {}s",
+                result.service.name, min_interval
+            );
+            continue;
+        }
+        service.last_slow_notified = Some(now);
+        alerts.push(*result);
+    }
+    alerts
+}
+
+// Shows a desktop notification for each service that went down or recovered
+fn notify_transitions(app: &tauri::AppHandle, changed: &[&CheckResult]) {
+    for result in changed {
+        let body = if result.healthy {
+            format!("✅ {} recovered", result.service.name)
+        } else {
+            format!("❌ {} went down", result.service.name)
+        };
+        if let Err(e) = app
+            .notification()
+            .builder()
+            .title("Uptime Watcher")
+            .body(&body)
+            .show()
+        {
+            warn!("Failed to show notification: {}", e);
+        }
+    }
+}
+
+// Early warning for services that still answer but have become slow
+fn notify_slow(app: &tauri::AppHandle, slow: &[&CheckResult]) {
+    for result in slow {
+        let body = format!(
+            "⚠ {} is slow: {} ms (limit {} ms)",
+            result.service.name,
+            result.latency_ms.unwrap_or_default(),
+            result.service.degraded_latency_ms.unwrap_or_default()
+        );
+        if let Err(e) = app
+            .notification()
+            .builder()
+            .title("Uptime Watcher")
+            .body(&body)
+            .show()
+        {
+            warn!("Failed to show notification: {}", e);
+        }
+    }
+}
+
+// Puts a service's host:port (or command) on the clipboard for pasting into a terminal
+fn copy_service_address(app: &tauri::AppHandle, index: usize) {
+    let address = match lock_or_recover(&app.state::<AppState>().data)
+        .services
+        .get(index)
+    {
+        Some(service) => service_address(service),
+        None => return,
+    };
+    if let Err(e) = app.clipboard().write_text(address.clone()) {
+        warn!("Failed to copy {} to the clipboard: {}", address, e);
+        return;
+    }
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title("Uptime Watcher")
+        .body(format!("Copied {}", address))
+        .show()
+    {
+        warn!("Failed to show notification: {}", e);
+    }
+}
+
+// Opens an HTTP(S) service's URL in the default browser
+fn open_service_url(app: &tauri::AppHandle, index: usize) {
+    let url = match lock_or_recover(&app.state::<AppState>().data)
+        .services
+        .get(index)
+    {
+        Some(service) => service_url(service),
+        None => return,
+    };
+    if let Err(e) = app.opener().open_url(&url, None::<&str>) {
+        warn!("Failed to open {}: {}", url, e);
+    }
+}
+
+// Players tried in turn for the alert sound, using what each platform ships with
+fn sound_players(path: &Path) -> Vec<Command> {
+    #[cfg(target_os = "macos")]
+    {
+        let mut afplay = Command::new("afplay");
+        afplay.arg(path);
+        vec![afplay]
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let mut powershell = Command::new("powershell");
+        powershell
+            .args(["-NoProfile", "-NonInteractive", "-Command"])
+            .arg(format!(
+                "(New-Object Media.SoundPlayer '{}').PlaySync()",
+                path.display().to_string().replace('\'', "''")
+            ))
+            .creation_flags(CREATE_NO_WINDOW);
+        vec![powershell]
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        ["paplay", "aplay"]
+            .iter()
+            .map(|player| {
+                let mut command = Command::new(player);
+                command.arg(path);
+                command
+            })
+            .collect()
+    }
+}
+
+// Plays the bundled alert sound in the background
+fn play_alert_sound(app: &tauri::AppHandle) {
+    let path = match app
+        .path()
+        .resolve("sounds/alert.wav", tauri::path::BaseDirectory::Resource)
+    {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("Failed to resolve alert sound: {}", e);
+            return;
+        }
+    };
+    thread::spawn(move || {
+        let played = sound_players(&path).into_iter().any(|mut player| {
+            player
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+        });
+        if !played {
+            warn!("Failed to play alert sound {:?}", path);
+        }
+    });
+}
+
+// Body of a webhook POST; `text` lets Slack-style endpoints show it as-is
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    service: &'a str,
+    old_state: HealthState,
+    new_state: HealthState,
+    timestamp: u64,
+    text: String,
+}
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+// The host and port of a URL, without user info, path or query
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    authority.rsplit('@').next().unwrap_or(authority)
+}
+
+// Scheme and host only; webhook paths and queries usually carry the secret token
+fn redact_url(url: &str) -> String {
+    match url.split_once("://") {
+        Some((scheme, _)) => format!("{}://{}", scheme, url_host(url)),
+        None => url_host(url).to_string(),
+    }
+}
+
+// The webhook body: the payload as JSON, or the template with each {{field}} replaced by the
+// payload's value, escaped so it can sit inside a JSON string
+fn render_webhook(template: Option<&str>, payload: &WebhookPayload) -> Result<String, String> {
+    let Some(template) = template else {
+        return serde_json::to_string(payload)
+            .map_err(|e| format!("Failed to serialize webhook payload: {}", e));
+    };
+    let fields = serde_json::to_value(payload)
+        .map_err(|e| format!("Failed to serialize webhook payload: {}", e))?;
+    let mut body = template.to_string();
+    for (key, value) in fields.as_object().into_iter().flatten() {
+        let text = match value {
+            serde_json::Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        let escaped = serde_json::to_string(&text).unwrap_or_default();
+        let escaped = &escaped[1..escaped.len() - 1];
+        body = body.replace(&format!("{{{{{}}}}}", key), escaped);
+    }
+    serde_json::from_str::<serde_json::Value>(&body)
+        .map_err(|e| format!("Webhook template is not valid JSON once filled in: {}", e))?;
+    Ok(body)
+}
+
+// Stand-in transition for checking a template without a real outage
+fn sample_webhook_payload() -> WebhookPayload<'static> {
+    WebhookPayload {
+        service: "Example service",
+        old_state: HealthState::Up,
+        new_state: HealthState::Down,
+        timestamp: now_secs(),
+        text: "❌ Example service went down".to_string(),
+    }
+}
+
+// Discord rejects longer message content
+const DISCORD_CONTENT_MAX_CHARS: usize = 2000;
+
+// Problems a known chat service would have with the body, which it would reject or drop
+fn webhook_shape_warnings(url: &str, body: &serde_json::Value) -> Vec<String> {
+    let host = url_host(url).to_ascii_lowercase();
+    let has =
+        |key: &str, is_kind: fn(&serde_json::Value) -> bool| body.get(key).is_some_and(is_kind);
+    let mut warnings = Vec::new();
+    if host == "hooks.slack.com" {
+        if !has("text", serde_json::Value::is_string) && !has("blocks", serde_json::Value::is_array)
+        {
+            warnings.push("Slack expects a \"text\" string or a \"blocks\" array".to_string());
+        }
+    } else if host == "discord.com" || host == "discordapp.com" || host.ends_with(".discord.com") {
+        if !has("content", serde_json::Value::is_string)
+            && !has("embeds", serde_json::Value::is_array)
+        {
+            warnings
+                .push("Discord expects a \"content\" string or an \"embeds\" array".to_string());
+        }
+        let content = body
+            .get("content")
+            .and_then(|c| c.as_str())
+            .unwrap_or_default();
+        if content.chars().count() > DISCORD_CONTENT_MAX_CHARS {
+            warnings.push(format!(
+                "Discord rejects \"content\" over {} characters",
+                DISCORD_CONTENT_MAX_CHARS
+            ));
+        }
+    }
+    warnings
+}
+
+fn post_webhook(url: &str, template: Option<&str>, payload: &WebhookPayload) -> Result<(), String> {
+    let body = render_webhook(template, payload)?;
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(WEBHOOK_TIMEOUT))
+        .build()
+        .into();
+    agent
+        .post(url)
+        .header("Content-Type", "application/json")
+        .send(&body)
+        .map(|_| ())
+        .map_err(|e| {
+            let redacted = redact_url(url);
+            let reason = e.to_string().replace(url, &redacted);
+            format!("Webhook {} failed: {}", redacted, reason)
+        })
+}
+
+// Posts each transition to the webhook off the check loop, so a slow endpoint can't delay it
+fn send_webhooks(
+    url: String,
+    template: Option<String>,
+    previous: &[CheckResult],
+    changed: &[&CheckResult],
+) {
+    let timestamp = now_secs();
+    let events: Vec<(String, HealthState, HealthState)> = changed
+        .iter()
+        .filter_map(|result| {
+            let old = previous
+                .iter()
+                .find(|prev| same_service(&prev.service, &result.service))?;
+            // A held-back alert can land a cycle after the flip it reports
+            let old_state = match (old.state == result.state, result.healthy) {
+                (false, _) => old.state,
+                (true, true) => HealthState::Down,
+                (true, false) => HealthState::Up,
+            };
+            Some((result.service.name.clone(), old_state, result.state))
+        })
+        .collect();
+    if events.is_empty() {
+        return;
+    }
+    thread::spawn(move || {
+        for (service, old_state, new_state) in &events {
+            let text = if *new_state == HealthState::Down {
+                format!("❌ {} went down", service)
+            } else {
+                format!("✅ {} recovered", service)
+            };
+            let payload = WebhookPayload {
+                service,
+                old_state: *old_state,
+                new_state: *new_state,
+                timestamp,
+                text,
+            };
+            if let Err(e) = post_webhook(&url, template.as_deref(), &payload) {
+                warn!("{}", e);
+            }
+        }
+    });
+}
+
+// A running local API; setting `stop` makes its thread exit within a poll interval
+struct ApiServer {
+    port: u16,
+    stop: Arc<AtomicBool>,
+}
+
+const API_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const API_READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Starts, stops or moves the local API to match the api_enabled and api_port settings
+fn configure_api_server(state: &AppState) -> Result<(), String> {
+    let (enabled, port) = {
+        let data = lock_or_recover(&state.data);
+        (data.api_enabled, data.api_port)
+    };
+    let mut server = lock_or_recover(&state.api_server);
+    if let Some(running) = server.as_ref() {
+        if enabled && running.port == port {
+            return Ok(());
+        }
+        running.stop.store(true, Ordering::SeqCst);
+        *server = None;
+        // Let the old thread drop its listener before the port is bound again
+        thread::sleep(API_POLL_INTERVAL * 2);
+    }
+    if enabled {
+        let stop = start_api_server(state.data.clone(), port)?;
+        *server = Some(ApiServer { port, stop });
+    }
+    Ok(())
+}
+
+// Only binds to loopback, the API has no authentication
+fn start_api_server(data: Arc<Mutex<AppStateData>>, port: u16) -> Result<Arc<AtomicBool>, String> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+        .map_err(|e| format!("Failed to start the local API on port {}: {}", port, e))?;
+    info!("Local API listening on 127.0.0.1:{}", port);
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    thread::spawn(move || {
+        while !stopped.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if let Err(e) = serve_api_request(&data, stream) {
+                        warn!("Local API request failed: {}", e);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(API_POLL_INTERVAL),
+                Err(e) => {
+                    warn!("Local API accept failed: {}", e);
+                    thread::sleep(API_POLL_INTERVAL);
+                }
+            }
+        }
+        info!("Local API on port {} stopped", port);
+    });
+    Ok(stop)
+}
+
+// Answers one request and closes the connection
+fn serve_api_request(data: &Mutex<AppStateData>, stream: TcpStream) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(API_READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are not used, but are read so the client sees a clean close
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (
+        parts.next().unwrap_or_default(),
+        parts.next().unwrap_or_default(),
+    );
+    let path = target.split('?').next().unwrap_or_default();
+    let (status, content_type, body) = api_response(&lock_or_recover(data), method, path);
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+// Escapes a Prometheus label value: backslash, double quote and newline
+fn prometheus_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// Results of the latest cycle in the Prometheus text exposition format. Failed checks have
+// no latency, so they only appear in uptime_service_up.
+fn prometheus_metrics(results: &[CheckResult]) -> String {
+    let mut up = String::from(
+        "# HELP uptime_service_up Whether the service passed its latest check.\n\
+         # TYPE uptime_service_up gauge\n",
+    );
+    let mut latency = String::from(
+        "# HELP uptime_service_latency_ms Duration of the latest successful check.\n\
+         # TYPE uptime_service_latency_ms gauge\n",
+    );
+    for result in results {
+        let name = prometheus_label(&result.service.name);
+        up.push_str(&format!(
+            "uptime_service_up{{name=\"{}\"}} {}\n",
+            name,
+            u8::from(result.healthy)
+        ));
+        if let Some(ms) = result.latency_ms {
+            latency.push_str(&format!(
+                "uptime_service_latency_ms{{name=\"{}\"}} {}\n",
+                name, ms
+            ));
+        }
+    }
+    up + &latency
+}
+
+// Status line, content type and body for a local API request
+fn api_response(
+    data: &AppStateData,
+    method: &str,
+    path: &str,
+) -> (&'static str, &'static str, String) {
+    const JSON: &str = "application/json";
+    const TEXT: &str = "text/plain; charset=utf-8";
+    match (method, path) {
+        ("GET", "/status") => match serde_json::to_string(&data.last_results) {
+            Ok(json) => ("200 OK", JSON, json),
+            Err(e) => ("500 Internal Server Error", TEXT, e.to_string()),
+        },
+        ("GET", "/metrics") => (
+            "200 OK",
+            "text/plain; version=0.0.4; charset=utf-8",
+            prometheus_metrics(&data.last_results),
+        ),
+        (_, "/status" | "/metrics") => (
+            "405 Method Not Allowed",
+            TEXT,
+            "Only GET is supported\n".to_string(),
+        ),
+        _ => ("404 Not Found", TEXT, "Not found\n".to_string()),
+    }
+}
+
+// Emits a check event per service plus a transition event when its health changed
+fn emit_cycle_events(
+    output: EventOutput,
+    events_path: &Path,
+    agent_id: &str,
+    previous: &[CheckResult],
+    current: &[CheckResult],
+) {
+    if output == EventOutput::Off {
+        return;
+    }
+    let ts = now_secs();
+
+    for result in current {
+        let (svc, healthy) = (&result.service, result.healthy);
+        let reason = (!healthy).then(|| reason_code(result));
+        write_structured_event(
+            output,
+            events_path,
+            &StructuredEvent {
+                ts,
+                level: if healthy { "info" } else { "warn" },
+                service: Some(&svc.name),
+                event: "check",
+                reason,
+                latency_ms: result.latency_ms,
+                injected: result.injected,
+                agent_id,
+            },
+        );
+
+        if let Some(was_healthy) = previous_health(previous, result).filter(|h| *h != healthy) {
+            let event = if was_healthy { "down" } else { "recovered" };
+            write_structured_event(
+                output,
+                events_path,
+                &StructuredEvent {
+                    ts,
+                    level: if healthy { "info" } else { "error" },
+                    service: Some(&svc.name),
+                    event,
+                    reason,
+                    latency_ms: result.latency_ms,
+                    injected: result.injected,
+                    agent_id,
+                },
+            );
+        }
+    }
+}
+
+// A fixed code for why a check failed. Error texts name hosts, addresses and command
+// output, so they never go into structured events.
+fn reason_code(result: &CheckResult) -> &'static str {
+    let error = result.error.as_deref().unwrap_or_default().to_lowercase();
+    match (result.status_code, result.cert_expires_at) {
+        (Some(_), _) if error.ends_with("body mismatch") => return "body_mismatch",
+        (Some(_), _) if error.contains("body read failed") => return "body_read",
+        (Some(_), _) if error.contains("healthz") => return "healthz_failed",
+        (Some(_), _) => return "http_status",
+        (None, Some(_)) => return "cert_expiring",
+        (None, None) => {}
+    }
+    if error.is_empty() {
+        return match result.service.protocol {
+            Protocol::Ping | Protocol::Udp => "no_reply",
+            _ => "connect_failed",
+        };
+    }
+    let reasons = [
+        ("check panicked", "check_panicked"),
+        ("command checks are disabled", "command_disabled"),
+        ("timed out", "timeout"),
+        ("resolve", "dns"),
+        ("refused", "refused"),
+        ("could not connect", "connect_failed"),
+        ("unreachable", "unreachable"),
+        ("privileges", "permission"),
+        ("requires admin", "permission"),
+        ("tls", "tls"),
+        ("certificate", "tls"),
+        ("exit ", "command_exit"),
+        ("hex", "invalid_payload"),
+        ("unexpected reply", "reply_mismatch"),
+        ("reply read failed", "body_read"),
+    ];
+    reasons
+        .iter()
+        .find(|(needle, _)| error.contains(needle))
+        .map_or("error", |(_, code)| code)
+}
+
+// The root URL of an HTTP or HTTPS service
+fn service_url(service: &Service) -> String {
+    let scheme = if service.protocol == Protocol::Https {
+        "https"
+    } else {
+        "http"
+    };
+    format!("{}://{}/", scheme, host_port(&service.host, &service.port))
+}
+
+// Only the start of the body is searched for the expected text
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+// Connect and read timeouts for a check, each falling back to the service's single timeout
+fn split_timeouts(service: &Service, timeout: Duration) -> (Duration, Duration) {
+    let or_timeout = |ms: Option<u64>| ms.map_or(timeout, Duration::from_millis);
+    (
+        or_timeout(service.connect_timeout_ms),
+        or_timeout(service.read_timeout_ms),
+    )
+}
+
+// A health endpoint returning JSON such as {"status": "ok", "checks": {...}}. The service
+// is up when the value at json_path equals expected.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct HealthzCheck {
+    path: String,      // e.g. "/healthz"
+    json_path: String, // e.g. "$.status" or "$.checks['db'].status"
+    expected: serde_json::Value,
+}
+
+#[derive(Debug, PartialEq)]
+enum JsonPathSegment {
+    Key(String),
+    Index(usize),
+}
+
+// The supported JSONPath subset: "$" followed by any of ".key", "['key']" and "[index]"
+fn parse_json_path(path: &str) -> Result<Vec<JsonPathSegment>, String> {
+    let invalid = || format!("'{}' is not a supported JSON path", path);
+    let mut rest = path.trim().strip_prefix('$').ok_or_else(invalid)?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            segments.push(JsonPathSegment::Key(after[..end].to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(invalid)?;
+            let inner = &after[..end];
+            let quoted = inner
+                .strip_prefix('\'')
+                .and_then(|k| k.strip_suffix('\''))
+                .or_else(|| inner.strip_prefix('"').and_then(|k| k.strip_suffix('"')));
+            segments.push(match quoted {
+                Some(key) => JsonPathSegment::Key(key.to_string()),
+                None => JsonPathSegment::Index(inner.parse().map_err(|_| invalid())?),
+            });
+            rest = &after[end + 1..];
+        } else {
+            return Err(invalid());
+        }
+    }
+    Ok(segments)
+}
+
+// Tests a health endpoint's body. The reason for a mismatch names the value found and, when
+// the body has a "checks" object, the sub-checks that aren't at the expected value either.
+fn evaluate_healthz(body: &str, check: &HealthzCheck) -> Result<(), String> {
+    let json: serde_json::Value =
+        serde_json::from_str(body).map_err(|_| "healthz body is not JSON".to_string())?;
+    let segments = parse_json_path(&check.json_path)?;
+    let actual = segments
+        .iter()
+        .try_fold(&json, |value, segment| match segment {
+            JsonPathSegment::Key(key) => value.get(key),
+            JsonPathSegment::Index(index) => value.get(index),
+        });
+    let actual = match actual {
+        Some(actual) if *actual == check.expected => return Ok(()),
+        Some(actual) => actual,
+        None => return Err(format!("healthz {} not found", check.json_path)),
+    };
+    let mut reason = format!(
+        "healthz {} is {}, expected {}",
+        check.json_path, actual, check.expected
+    );
+    let failing: Vec<&str> = json
+        .get("checks")
+        .and_then(|checks| checks.as_object())
+        .into_iter()
+        .flatten()
+        .filter(|(_, sub)| **sub != check.expected && sub.get("status") != Some(&check.expected))
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if !failing.is_empty() {
+        reason.push_str(&format!(" (failing: {})", failing.join(", ")));
+    }
+    Err(reason)
+}
+
+fn validate_healthz(check: &HealthzCheck) -> Result<(), String> {
+    if !check.path.starts_with('/') {
+        return Err(format!("Health path '{}' must start with /", check.path));
+    }
+    parse_json_path(&check.json_path).map(|_| ())
+}
+
+// Requests GET / and treats 2xx/3xx as healthy, returning the status code if one came back.
+// With an expected body substring the response must also contain it, else "body mismatch".
+// A healthz check requests its path instead and must pass evaluate_healthz.
+fn http_check(service: &Service, timeout: Duration) -> (bool, Option<u16>, Option<String>) {
+    let mut url = service_url(service);
+    if let Some(healthz) = &service.healthz {
+        url.push_str(healthz.path.trim_start_matches('/'));
+    }
+    let (connect_timeout, read_timeout) = split_timeouts(service, timeout);
+    // Without a split the single timeout bounds the whole request, as it always has
+    let overall = match (service.connect_timeout_ms, service.read_timeout_ms) {
+        (None, None) => timeout,
+        _ => connect_timeout + read_timeout,
+    };
+
+    // Redirects are reported as-is rather than followed, and error statuses are not errors
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(overall))
+        .timeout_connect(Some(connect_timeout))
+        .timeout_recv_response(Some(read_timeout))
+        .timeout_recv_body(Some(read_timeout))
+        .http_status_as_error(false)
+        .max_redirects(0)
+        .build()
+        .into();
+
+    match agent.get(&url).call() {
+        Ok(mut response) => {
+            let code = response.status().as_u16();
+            if !(200..400).contains(&code) {
+                return (false, Some(code), None);
+            }
+            if service.healthz.is_none() && service.expected_body_substring.is_none() {
+                return (true, Some(code), None);
+            }
+            let mut body = Vec::new();
+            if let Err(e) = response
+                .body_mut()
+                .as_reader()
+                .take(MAX_BODY_BYTES)
+                .read_to_end(&mut body)
+            {
+                return (false, Some(code), Some(format!("body read failed: {}", e)));
+            }
+            let body = String::from_utf8_lossy(&body);
+            if let Some(healthz) = &service.healthz {
+                if let Err(reason) = evaluate_healthz(&body, healthz) {
+                    return (false, Some(code), Some(reason));
+                }
+            }
+            let expected = service
+                .expected_body_substring
+                .as_deref()
+                .unwrap_or_default();
+            if body.contains(expected) {
+                (true, Some(code), None)
+            } else {
+                (false, Some(code), Some("body mismatch".to_string()))
+            }
+        }
+        Err(e) => {
+            warn!("HTTP check of {} failed: {}", url, e);
+            (false, None, None)
+        }
+    }
+}
+
+// How a service is shown in logs and copied from the menu
+fn service_address(service: &Service) -> String {
+    if service.protocol == Protocol::Command {
+        service.command.clone()
+    } else {
+        host_port(&service.host, &service.port)
+    }
+}
+
+// Longer command output is cut off, it only has to fit the management window
+const MAX_COMMAND_OUTPUT_CHARS: usize = 4096;
+
+// Runs a command through the platform shell, healthy on exit code 0, and returns what it
+// printed alongside. A command still running at the timeout is killed. The command and its
+// output can hold secrets, so they are only logged with verbose logging on.
+fn command_check(
+    command: &str,
+    timeout: Duration,
+    verbose: bool,
+) -> (Result<bool, String>, Option<String>) {
+    #[cfg(windows)]
+    let mut shell = {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]).creation_flags(CREATE_NO_WINDOW);
+        shell
+    };
+    #[cfg(not(windows))]
+    let mut shell = {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    };
+    let child = shell
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => return (Err(format!("Failed to run command: {}", e)), None),
+    };
+
+    // Drain both pipes while waiting so a chatty command can't block on a full pipe
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut output = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut output);
+            }
+            output
+        })
+    };
+    let stdout = drain(
+        child
+            .stdout
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+    let stderr = drain(
+        child
+            .stderr
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                let error = format!("Timed out after {} ms", timeout.as_millis());
+                return (Err(error), None);
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(e) => return (Err(format!("Failed to wait for command: {}", e)), None),
+        }
+    };
+
+    let output: Vec<String> = [stdout, stderr]
+        .into_iter()
+        .filter_map(|reader| reader.join().ok())
+        .map(|bytes| String::from_utf8_lossy(&bytes).trim().to_string())
+        .filter(|text| !text.is_empty())
+        .collect();
+    let output = (!output.is_empty()).then(|| {
+        output
+            .join("\n")
+            .chars()
+            .take(MAX_COMMAND_OUTPUT_CHARS)
+            .collect()
+    });
+    let code = status
+        .code()
+        .map_or("a signal".to_string(), |code| format!("code {}", code));
+    if verbose {
+        info!(
+            "Command `{}` exited with {}: {}",
+            command,
+            code,
+            output.as_deref().unwrap_or_default()
+        );
+    }
+    if status.success() {
+        (Ok(true), output)
+    } else {
+        (Err(format!("exit {}", code)), output)
+    }
+}
+
+// Internet checksum over an ICMPv4 message
+fn icmp_checksum(packet: &[u8]) -> u16 {
+    let mut sum: u32 = packet
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]) as u32)
+        .sum();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+// Sends one ICMP echo request and waits for the reply. Err means the ping could not be sent.
+fn ping_check(host: &str, timeout: Duration) -> Result<bool, String> {
+    let addr: IpAddr = match bare_host(host).parse() {
+        Ok(addr) => addr,
+        Err(_) => resolve(host, "0")?[0].ip(),
+    };
+    let (domain, protocol, request_type, reply_type) = match addr {
+        IpAddr::V4(_) => (Domain::IPV4, socket2::Protocol::ICMPV4, 8u8, 0u8),
+        IpAddr::V6(_) => (Domain::IPV6, socket2::Protocol::ICMPV6, 128, 129),
+    };
+
+    // Unprivileged datagram ICMP where the OS allows it, raw sockets otherwise
+    let socket = Socket::new(domain, Type::DGRAM, Some(protocol))
+        .or_else(|_| Socket::new(domain, Type::RAW, Some(protocol)))
+        .map_err(|e| match e.kind() {
+            ErrorKind::PermissionDenied if cfg!(windows) => {
+                "ping requires admin on Windows".to_string()
+            }
+            ErrorKind::PermissionDenied => "ping requires elevated privileges".to_string(),
+            _ => format!("Failed to open ICMP socket: {}", e),
+        })?;
+
+    let sequence: u16 = rand::random();
+    let mut packet = vec![request_type, 0, 0, 0, 0x55, 0x57];
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(b"uptime-watcher");
+    // The kernel fills in the ICMPv6 checksum itself
+    if addr.is_ipv4() {
+        let checksum = icmp_checksum(&packet);
+        packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+    }
+
+    // Connecting filters incoming replies to the target host
+    let target = SocketAddr::new(addr, 0);
+    socket
+        .connect(&target.into())
+        .and_then(|_| socket.send(&packet))
+        .map_err(|e| format!("Failed to send ping: {}", e))?;
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; 1024];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(false);
+        }
+        let _ = socket.set_read_timeout(Some(remaining));
+        let len = match (&socket).read(&mut buf) {
+            Ok(len) => len,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Ok(false)
+            }
+            Err(e) => return Err(format!("Failed to receive ping reply: {}", e)),
+        };
+
+        // IPv4 raw sockets (and datagram sockets on macOS) include the IP header
+        let reply = match buf[..len].first() {
+            Some(first) if addr.is_ipv4() && first >> 4 == 4 => {
+                &buf[((first & 0x0f) as usize * 4).min(len)..len]
+            }
+            _ => &buf[..len],
+        };
+        if reply.len() >= 8 && reply[0] == reply_type && reply[6..8] == sequence.to_be_bytes() {
+            return Ok(true);
+        }
+    }
+}
+
+// Decodes a hex string such as "de ad be ef", ignoring whitespace
+fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<u8> = hex.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("'{}' has an odd number of hex digits", hex));
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| format!("'{}' is not valid hex", hex))
+        })
+        .collect()
+}
+
+// A query for the root NS records, which any DNS server answers in some form
+fn dns_probe() -> Vec<u8> {
+    let id: u16 = rand::random();
+    let mut query = id.to_be_bytes().to_vec();
+    // Recursion desired, one question, root name, type NS, class IN
+    query.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 1]);
+    query
+}
+
+// Sends the probe to each resolved address and waits for any reply.
+// UDP has no handshake, so silence is ambiguous (dropped, filtered or just ignored
+// by the service); it is treated as down.
+fn udp_check(service: &Service, timeout: Duration) -> Result<bool, String> {
+    let mut payload = decode_hex(&service.udp_payload)?;
+    if payload.is_empty() && service.port == "53" {
+        payload = dns_probe();
+    }
+
+    for addr in resolve(&service.host, &service.port)? {
+        let local: SocketAddr = if addr.is_ipv4() {
+            "0.0.0.0:0".parse().unwrap()
+        } else {
+            "[::]:0".parse().unwrap()
+        };
+        let socket =
+            UdpSocket::bind(local).map_err(|e| format!("Failed to bind UDP socket: {}", e))?;
+        let _ = socket.set_read_timeout(Some(split_timeouts(service, timeout).1));
+        // Connecting lets an ICMP "port unreachable" surface as an error on recv
+        if socket.connect(addr).is_err() || socket.send(&payload).is_err() {
+            continue;
+        }
+        let mut reply = [0u8; 1500];
+        if socket.recv(&mut reply).is_ok() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Accepts any certificate, so expiry can still be read from expired or self-signed ones.
+// Only used by TLS expiry checks, which never send data over the connection.
+#[derive(Debug)]
+struct AcceptAnyCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+// Performs a TLS handshake and returns when the server's certificate expires (unix seconds)
+fn tls_cert_expiry(service: &Service, timeout: Duration) -> Result<u64, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS setup failed: {}", e))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AcceptAnyCert(provider)))
+        .with_no_client_auth();
+    let server_name = ServerName::try_from(bare_host(&service.host).to_string())
+        .map_err(|_| format!("'{}' is not a valid TLS server name", service.host))?;
+    let mut conn = ClientConnection::new(Arc::new(config), server_name)
+        .map_err(|e| format!("TLS setup failed: {}", e))?;
+
+    let (connect_timeout, read_timeout) = split_timeouts(service, timeout);
+    let addrs = resolve(&service.host, &service.port)?;
+    let mut stream = addrs
+        .iter()
+        .find_map(|addr| TcpStream::connect_timeout(addr, connect_timeout).ok())
+        .ok_or_else(|| format!("Could not connect to {}:{}", service.host, service.port))?;
+    // The read timeout also bounds each read and write of the handshake
+    let _ = stream.set_read_timeout(Some(read_timeout));
+    let _ = stream.set_write_timeout(Some(read_timeout));
+    while conn.is_handshaking() {
+        conn.complete_io(&mut stream)
+            .map_err(|e| format!("TLS handshake failed: {}", e))?;
+    }
+
+    let der = conn
+        .peer_certificates()
+        .and_then(|certs| certs.first())
+        .ok_or("Server sent no certificate")?;
+    let (_, cert) = x509_parser::parse_x509_certificate(der)
+        .map_err(|e| format!("Unreadable certificate: {}", e))?;
+    Ok(cert.validity().not_after.timestamp().max(0) as u64)
+}
+
+// Compact duration for menu labels, e.g. "45s", "3m 12s", "2h 5m" or "1d 4h"
+fn format_duration(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        3600..86400 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+// Whole days until the certificate expires, negative once it has
+fn cert_days_left(expires_at: u64) -> i64 {
+    (expires_at as i64 - now_secs() as i64).div_euclid(86400)
+}
+
+// Settings that apply to every check in a cycle
+#[derive(Clone, Copy)]
+struct CheckSettings {
+    default_timeout_ms: u64, // For services without their own timeout_ms
+    cert_warn_days: u64,
+    retry_count: u32,
+    retry_delay_ms: u64,
+    max_concurrent_checks: usize,
+    allow_command_checks: bool,
+    check_jitter_ms: u64,
+    verbose_logging: bool,
+}
+
+fn check_settings(data: &AppStateData) -> CheckSettings {
+    CheckSettings {
+        default_timeout_ms: data.default_timeout_ms,
+        cert_warn_days: data.cert_warn_days,
+        retry_count: data.retry_count,
+        retry_delay_ms: data.retry_delay_ms,
+        max_concurrent_checks: data.max_concurrent_checks,
+        allow_command_checks: data.allow_command_checks,
+        check_jitter_ms: data.check_jitter_ms,
+        verbose_logging: data.verbose_logging,
+    }
+}
+
+// Result of a single check attempt: health, HTTP status, error, certificate expiry and
+// command output
+type Attempt = (
+    bool,
+    Option<u16>,
+    Option<String>,
+    Option<u64>,
+    Option<String>,
+);
+
+// A second check a service only passes if it also passes, e.g. TCP to the API and then its
+// /healthz. Host and port default to the service's own.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct CheckStep {
+    name: String, // Names the step in the reason when it fails
+    protocol: Protocol,
+    #[serde(default)]
+    host: Option<String>,
+    #[serde(default)]
+    port: Option<String>,
+    #[serde(default)]
+    expected_body_substring: Option<String>,
+    #[serde(default)]
+    healthz: Option<HealthzCheck>,
+    #[serde(default)]
+    command: String,
+}
+
+fn validate_check_step(step: &CheckStep) -> Result<(), String> {
+    if step.name.trim().is_empty() {
+        return Err("The secondary check needs a name".to_string());
+    }
+    if step.protocol == Protocol::Command && step.command.trim().is_empty() {
+        return Err(format!("Step '{}' has an empty command", step.name));
+    }
+    if let Some(port) = &step.port {
+        match port.trim().parse::<u16>() {
+            Ok(port) if port > 0 => {}
+            _ => return Err(format!("'{}' is not a valid port (1-65535)", port)),
+        }
+    }
+    if let Some(healthz) = &step.healthz {
+        if !matches!(step.protocol, Protocol::Http | Protocol::Https) {
+            return Err("Healthz checks need an HTTP or HTTPS step".to_string());
+        }
+        validate_healthz(healthz)?;
+    }
+    Ok(())
+}
+
+// The service as the step checks it
+fn step_service(service: &Service, step: &CheckStep) -> Service {
+    Service {
+        protocol: step.protocol,
+        host: step.host.clone().unwrap_or_else(|| service.host.clone()),
+        port: step.port.clone().unwrap_or_else(|| service.port.clone()),
+        expected_body_substring: step.expected_body_substring.clone(),
+        healthz: step.healthz.clone(),
+        command: step.command.clone(),
+        tcp_exchange: None,
+        secondary: None,
+        ..service.clone()
+    }
+}
+
+// What went wrong in a failed attempt, for naming it in a step's reason
+fn attempt_detail(attempt: &Attempt) -> String {
+    match attempt {
+        (_, _, Some(error), _, _) => error.clone(),
+        (_, Some(code), None, _, _) => format!("HTTP {}", code),
+        (_, None, None, Some(_), _) => "certificate expires soon".to_string(),
+        (_, None, None, None, _) => "no answer".to_string(),
+    }
+}
+
+// Runs the secondary step only when the primary attempt passed. Whichever step failed is
+// named in the error; the primary's certificate expiry is kept either way.
+fn combine_steps(
+    primary: Attempt,
+    step_name: &str,
+    secondary: impl FnOnce() -> Attempt,
+) -> Attempt {
+    if !primary.0 {
+        let error = format!("primary check failed: {}", attempt_detail(&primary));
+        return (false, primary.1, Some(error), primary.3, primary.4);
+    }
+    let step = secondary();
+    if step.0 {
+        return primary;
+    }
+    let error = format!("step '{}' failed: {}", step_name, attempt_detail(&step));
+    (false, step.1, Some(error), primary.3, step.4.or(primary.4))
+}
+
+// The service's own check, then its secondary step if it has one
+fn probe_steps(service: &Service, timeout: Duration, settings: CheckSettings) -> Attempt {
+    let primary = probe(service, timeout, settings);
+    match &service.secondary {
+        Some(step) => combine_steps(primary, &step.name, || {
+            probe(&step_service(service, step), timeout, settings)
+        }),
+        None => primary,
+    }
+}
+
+// Runs the protocol's check once
+fn probe(service: &Service, timeout: Duration, settings: CheckSettings) -> Attempt {
+    let outcome = |result: Result<bool, String>| match result {
+        Ok(healthy) => (healthy, None, None, None, None),
+        Err(e) => (false, None, Some(e), None, None),
+    };
+    match service.protocol {
+        Protocol::Tcp => match &service.tcp_exchange {
+            Some(exchange) => outcome(tcp_exchange_check(service, exchange, timeout)),
+            None => outcome(tcp_connect(
+                &service.host,
+                &service.port,
+                split_timeouts(service, timeout).0,
+            )),
+        },
+        Protocol::Http | Protocol::Https => {
+            let (healthy, code, error) = http_check(service, timeout);
+            (healthy, code, error, None, None)
+        }
+        Protocol::Ping => outcome(ping_check(&service.host, timeout)),
+        Protocol::Udp => outcome(udp_check(service, timeout)),
+        Protocol::Tls => match tls_cert_expiry(service, timeout) {
+            Ok(expires_at) => {
+                let healthy = cert_days_left(expires_at) >= settings.cert_warn_days as i64;
+                (healthy, None, None, Some(expires_at), None)
+            }
+            Err(e) => (false, None, Some(e), None, None),
+        },
+        Protocol::Command if !settings.allow_command_checks => (
+            false,
+            None,
+            Some("Command checks are disabled".to_string()),
+            None,
+            None,
+        ),
+        Protocol::Command => {
+            let (result, output) =
+                command_check(&service.command, timeout, settings.verbose_logging);
+            let (healthy, _, error, _, _) = outcome(result);
+            (healthy, None, error, None, output)
+        }
+    }
+}
+
+// Checks a single service, retrying failures and applying any injected fault
+fn check_service(
+    service: &Service,
+    fault: Option<&FaultInjection>,
+    adaptive_timeout_ms: Option<u64>,
+    settings: CheckSettings,
+) -> CheckResult {
+    // Commands can hold credentials, they are only logged with verbose logging on
+    let address = match service.protocol {
+        Protocol::Command if !settings.verbose_logging => "command".to_string(),
+        _ => service_address(service),
+    };
+    let timeout_ms = adaptive_timeout_ms
+        .or(service.timeout_ms)
+        .unwrap_or(settings.default_timeout_ms);
+    let timeout = Duration::from_millis(timeout_ms);
+
+    // Spreads out services that share an interval; not part of the measured latency
+    if settings.check_jitter_ms > 0 {
+        thread::sleep(Duration::from_millis(rand::random_range(
+            0..=settings.check_jitter_ms,
+        )));
+    }
+
+    let mut attempt = 0;
+    let (is_healthy, status_code, error, cert_expires_at, output, dropped, latency_ms) = loop {
+        // Injected latency counts towards the measurement, as real slowness would
+        let start = Instant::now();
+        if let Some(fault) = fault {
+            thread::sleep(Duration::from_millis(fault.added_latency_ms));
+        }
+        let (mut is_healthy, status_code, error, cert_expires_at, output) =
+            probe_steps(service, timeout, settings);
+
+        let mut dropped = false;
+        if let Some(fault) = fault {
+            if is_healthy && rand::random::<f64>() * 100.0 < fault.loss_pct {
+                is_healthy = false;
+                dropped = true;
+            }
+        }
+        let latency_ms = is_healthy.then(|| start.elapsed().as_millis());
+
+        // A certificate close to expiry won't change by trying again
+        if is_healthy || cert_expires_at.is_some() || attempt >= settings.retry_count {
+            break (
+                is_healthy,
+                status_code,
+                error,
+                cert_expires_at,
+                output,
+                dropped,
+                latency_ms,
+            );
+        }
+        attempt += 1;
+        thread::sleep(Duration::from_millis(settings.retry_delay_ms));
+    };
+
+    if is_healthy && attempt > 0 {
+        warn!(
+            "⚠ {} ({}) is up after {} failed attempt(s)",
+            service.name, address, attempt
+        );
+    } else if dropped {
+        warn!("❌ {} ({}) is DOWN [fault injected]", service.name, address);
+    } else if let (Some(code), Some(e)) = (status_code, &error) {
+        warn!(
+            "❌ {} ({}) is DOWN: {} ({})",
+            service.name, address, e, code
+        );
+    } else if let Some(e) = &error {
+        warn!(
+            "❌ {} ({}) could not be checked: {}",
+            service.name, address, e
+        );
+    } else if let (false, Some(expires_at)) = (is_healthy, cert_expires_at) {
+        warn!(
+            "⚠ {} ({}) certificate expires in {} days",
+            service.name,
+            address,
+            cert_days_left(expires_at)
+        );
+    } else if !is_healthy {
+        warn!("❌ {} ({}) is DOWN", service.name, address);
+    }
+
+    let slow = too_slow(service, latency_ms);
+    let redirected = status_code.is_some_and(|code| (300..400).contains(&code));
+    // Expected downtime is still checked and logged, but doesn't alert or turn the tray red
+    let maintenance = !is_healthy && in_maintenance(&service.maintenance_windows);
+    let state = if maintenance {
+        info!(
+            "🔧 {} ({}) is in a maintenance window",
+            service.name, address
+        );
+        HealthState::Maintenance
+    } else if !is_healthy {
+        HealthState::Down
+    } else if slow || redirected {
+        HealthState::Degraded
+    } else {
+        HealthState::Up
+    };
+
+    CheckResult {
+        service: service.clone(),
+        healthy: is_healthy || maintenance,
+        state,
+        latency_ms,
+        status_code,
+        error,
+        cert_expires_at,
+        injected: fault.is_some(),
+        output,
+    }
+}
+
+// Checks the services marked in `due` in parallel, at most `max_concurrent_checks` at a
+// time, and returns their results in order
+fn check_lab_status(
+    services: &[Service],
+    due: &[bool],
+    faults: &HashMap<ServiceKey, FaultInjection>,
+    timeouts: &HashMap<ServiceKey, u64>,
+    settings: CheckSettings,
+) -> Vec<CheckResult> {
+    let checked: Vec<&Service> = services
+        .iter()
+        .zip(due)
+        .filter(|(_, due)| **due)
+        .map(|(service, _)| service)
+        .collect();
+
+    // A fixed number of workers take the next unchecked service until none are left
+    let next = AtomicUsize::new(0);
+    let slots: Vec<Mutex<Option<CheckResult>>> = checked.iter().map(|_| Mutex::new(None)).collect();
+    let workers = settings
+        .max_concurrent_checks
+        .clamp(1, checked.len().max(1));
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let Some(&service) = checked.get(i) else {
+                    break;
+                };
+                let key = service_key(service);
+                let (fault, timeout_ms) = (faults.get(&key), timeouts.get(&key).copied());
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    check_service(service, fault, timeout_ms, settings)
+                }));
+                if let (Ok(result), Ok(mut slot)) = (result, slots[i].lock()) {
+                    *slot = Some(result);
+                }
+            });
+        }
+    });
+
+    let results: Vec<CheckResult> = slots
+        .into_iter()
+        .zip(&checked)
+        .map(|(slot, &service)| {
+            slot.into_inner()
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| CheckResult {
+                    service: service.clone(),
+                    healthy: false,
+                    state: HealthState::Down,
+                    latency_ms: None,
+                    status_code: None,
+                    error: Some("check panicked".to_string()),
+                    cert_expires_at: None,
+                    injected: false,
+                    output: None,
+                })
+        })
+        .collect();
+
+    // Only print if everything is okay
+    if results.iter().all(|r| r.healthy) && !results.is_empty() {
+        info!("✅ All Systems Normal");
+    }
+
+    results
+}
+
+// Cycles save the stats they counted at most this often, so a crash loses a few minutes
+const STATS_SAVE_INTERVAL_SECS: u64 = 300;
+
+// Stamps each checked service and counts the check in its stats, skipping any that were
+// edited while the cycle ran. Fault-injected checks are stamped but not counted.
+fn mark_checked(services: &mut [Service], results: &[CheckResult], timestamp: u64) {
+    for service in services.iter_mut() {
+        if let Some(result) = results.iter().find(|r| same_service(&r.service, service)) {
+            service.last_checked = Some(timestamp);
+            if result.injected {
+                continue;
+            }
+            service.stats.record(result.healthy);
+            if result.healthy {
+                service.down_since = None;
+            } else {
+                service.down_since.get_or_insert(timestamp);
+            }
+        }
+    }
+}
+
+// Payload of the "health-update" event sent to the frontend after every cycle
+#[derive(Clone, Serialize)]
+struct HealthUpdate {
+    timestamp: u64,
+    results: Vec<CheckResult>,
+}
+
+// How often a service is checked; down services are polled faster so a recovery shows up quickly
+fn effective_interval(
+    service: &Service,
+    previous: Option<&CheckResult>,
+    interval: u64,
+    down_recheck: u64,
+) -> u64 {
+    let interval = service.interval_secs.unwrap_or(interval);
+    if down_recheck > 0 && previous.is_some_and(|r| !r.healthy) {
+        interval.min(down_recheck)
+    } else {
+        interval
+    }
+}
+
+// Bounds for the monitoring loop's sleep. The upper one is how long a new service or
+// settings change can wait to be picked up.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// How long the monitoring loop can sleep before the next service is due
+fn next_check_delay(data: &AppStateData) -> Duration {
+    if data.monitoring_paused {
+        return MAX_POLL_INTERVAL;
+    }
+    let now = now_secs();
+    data.services
+        .iter()
+        .filter(|service| service.enabled)
+        .map(|service| {
+            let previous = data
+                .last_results
+                .iter()
+                .find(|r| same_service(&r.service, service));
+            match (previous, service.last_checked) {
+                (Some(_), Some(last)) => {
+                    let interval = effective_interval(
+                        service,
+                        previous,
+                        data.interval_secs,
+                        data.down_recheck_secs,
+                    );
+                    Duration::from_secs((last + interval).saturating_sub(now))
+                }
+                _ => Duration::ZERO,
+            }
+        })
+        .min()
+        .unwrap_or(MAX_POLL_INTERVAL)
+        .clamp(MIN_POLL_INTERVAL, MAX_POLL_INTERVAL)
+}
+
+// Which services a check cycle runs
+#[derive(Clone, Copy, PartialEq)]
+enum CycleScope {
+    Due,  // Those whose interval has passed, the monitoring loop
+    All,  // Every enabled service, check_now
+    Down, // Only services that are currently down, recheck_down
+}
+
+// Checks the services in `scope` and updates state,
+// history, events, notifications and the tray. Returns the latest result of every
+// enabled service, or nothing if no service was due.
+fn run_check_cycle(
+    handle: &tauri::AppHandle,
+    state: &AppState,
+    scope: CycleScope,
+) -> Vec<CheckResult> {
+    // Nothing new starts once the app is quitting
+    if !state.running.load(Ordering::SeqCst) {
+        return Vec::new();
+    }
+    let shared_data = &state.data;
+    let (events_path, history_file) = {
+        let path = lock_or_recover(&state.file_path);
+        (path.with_file_name("events.jsonl"), history_path(&path))
+    };
+
+    // 1. Get current service list and work out which services are due
+    let started = now_secs();
+    let (
+        services,
+        previous_results,
+        icon_set,
+        template,
+        faults,
+        timeouts,
+        settings,
+        interval,
+        down_recheck,
+        first,
+    ) = {
+        let mut data = lock_or_recover(shared_data);
+        if data.monitoring_paused {
+            return Vec::new();
         }
-        Err(e) => println!("Failed to serialize state: {}", e),
+        drop_orphaned_overrides(&mut data);
+        let timeouts: HashMap<ServiceKey, u64> = data
+            .services
+            .iter()
+            .filter_map(|service| {
+                let key = service_key(service);
+                let timeout = adaptive_timeout_ms(
+                    service.adaptive_timeout.as_ref()?,
+                    data.latency_samples.get(&key),
+                )?;
+                Some((key, timeout))
+            })
+            .collect();
+        (
+            data.services.clone(),
+            data.last_results.clone(),
+            data.icon_set.clone(),
+            template_mode(&data),
+            data.faults.clone(),
+            timeouts,
+            check_settings(&data),
+            data.interval_secs,
+            data.down_recheck_secs,
+            data.last_check_ts.is_none(),
+        )
+    };
+    let previous_result = |service: &Service| {
+        previous_results
+            .iter()
+            .find(|r| same_service(&r.service, service))
+    };
+    // A service without a previous result (new or just edited) is always due
+    let due: Vec<bool> = services
+        .iter()
+        .map(|service| {
+            let interval =
+                effective_interval(service, previous_result(service), interval, down_recheck);
+            let previous = previous_result(service);
+            service.enabled
+                && match scope {
+                    CycleScope::All => true,
+                    CycleScope::Down => previous.is_some_and(|r| !r.healthy),
+                    CycleScope::Due => {
+                        previous.is_none()
+                            || service
+                                .last_checked
+                                .is_none_or(|last| started.saturating_sub(last) >= interval)
+                    }
+                }
+        })
+        .collect();
+    // Removing or disabling a service still needs the tray to catch up
+    let enabled_count = services.iter().filter(|s| s.enabled).count();
+    if !due.contains(&true) && !first && previous_results.len() == enabled_count {
+        return Vec::new();
     }
-}
 
-// Helper to update tray icon
-fn update_tray_icon(app: &tauri::AppHandle, icon_set: &str, is_healthy: bool) {
-    if let Ok(resource_path) = app
-        .path()
-        .resolve("icons", tauri::path::BaseDirectory::Resource)
-    {
-        // println!("Resolved icons path: {:?}", resource_path);
-        let icon_name = if icon_set == "alt" {
-            if is_healthy {
-                "checked.png"
-            } else {
-                "cross.png"
-            }
-        } else {
-            // Default
-            if is_healthy {
-                "green.png"
-            } else {
-                "red.png"
-            }
-        };
+    // 2. Run the checks
+    let fresh_results = check_lab_status(&services, &due, &faults, &timeouts, settings);
+    check_mirrors(shared_data, &timeouts, settings);
+    warn_on_fd_pressure();
+    append_history(&history_file, &fresh_results);
 
-        let icon_path = resource_path.join(icon_name);
+    // Services that weren't due keep their latest result
+    let health_results: Vec<CheckResult> = services
+        .iter()
+        .filter(|service| service.enabled)
+        .filter_map(|service| {
+            fresh_results
+                .iter()
+                .find(|r| same_service(&r.service, service))
+                .or_else(|| previous_result(service))
+                .cloned()
+        })
+        .collect();
 
-        if let Ok(icon_bytes) = fs::read(&icon_path) {
-            if let Ok(icon) = Image::from_bytes(&icon_bytes) {
-                if let Some(tray) = app.tray_by_id("main") {
-                    let is_template = icon_set == "alt";
-                    // Set icon first, then template status. Some platforms reset status on icon change.
-                    let _ = tray.set_icon(Some(icon));
-                    let _ = tray.set_icon_as_template(is_template);
-                } else {
-                    println!("Failed to find tray by id 'main'");
-                }
-            } else {
-                println!("Failed to parse icon image");
+    // Store current health status in state for immediate updates
+    let timestamp = now_secs();
+    let (
+        status,
+        entries,
+        output,
+        agent_id,
+        notifications_enabled,
+        sound_enabled,
+        webhook,
+        previous,
+    ) = {
+        let mut data = lock_or_recover(shared_data);
+        let is_overall_healthy = overall_healthy(&health_results, data.health_policy);
+        record_overall_health(&mut data, is_overall_healthy);
+        let previous = std::mem::replace(&mut data.last_results, health_results.clone());
+        data.last_check_ts = Some(timestamp);
+        data.cycle_revision += 1;
+        mark_checked(&mut data.services, &fresh_results, started);
+        record_latencies(&mut data.latency_samples, &fresh_results);
+        // An open edit saves everything on commit, and saving now would write its staged changes
+        let counted = fresh_results.iter().any(|r| !r.injected);
+        if counted
+            && data.edit_txn.is_none()
+            && timestamp.saturating_sub(data.stats_saved_at) >= STATS_SAVE_INTERVAL_SECS
+        {
+            // save_state logs a failed write, which is retried next cycle
+            if save_state(&data, &lock_or_recover(&state.file_path)).is_ok() {
+                data.stats_saved_at = timestamp;
             }
-        } else {
-            println!("Failed to read icon file: {:?}", icon_path);
         }
+        (
+            tray_status(&data),
+            tray_menu_entries(&data),
+            data.structured_event_output,
+            data.agent_id.clone(),
+            data.notifications_enabled && snooze_left(&data).is_none(),
+            data.sound_enabled && snooze_left(&data).is_none(),
+            data.webhook_url
+                .clone()
+                .filter(|_| snooze_left(&data).is_none())
+                .map(|url| (url, data.webhook_template.clone())),
+            previous,
+        )
+    };
+
+    let changed = transitions(&previous, &fresh_results);
+    let (alerts, slow, escalations) = {
+        let mut data = lock_or_recover(shared_data);
+        let min_interval = data.notification_min_interval_secs;
+        let alerts = rate_limited_alerts(
+            &mut data.services,
+            &fresh_results,
+            &changed,
+            min_interval,
+            timestamp,
+        );
+        let slow = slow_transitions(&previous, &fresh_results);
+        let slow = rate_limited_slow(&mut data.services, &slow, min_interval, timestamp);
+        (alerts, slow, data.escalations.clone())
+    };
+    // Services can opt out of desktop alerts or webhooks while still reporting everywhere
+    // else, and be escalated back into them for a while
+    let escalations = &escalations;
+    let on = |channel: AlertChannel| {
+        move |r: &&CheckResult| alerts_on(escalations, &r.service, channel, timestamp)
+    };
+    let desktop_alerts: Vec<&CheckResult> = alerts
+        .iter()
+        .copied()
+        .filter(on(AlertChannel::Desktop))
+        .collect();
+    if notifications_enabled {
+        notify_transitions(handle, &desktop_alerts);
+        let slow: Vec<&CheckResult> = slow.into_iter().filter(on(AlertChannel::Desktop)).collect();
+        notify_slow(handle, &slow);
+    }
+    if sound_enabled
+        && alerts
+            .iter()
+            .copied()
+            .filter(on(AlertChannel::Sound))
+            .any(|r| !r.healthy)
+    {
+        play_alert_sound(handle);
+    }
+    if let Some((url, template)) = webhook {
+        let webhook_alerts: Vec<&CheckResult> = alerts
+            .iter()
+            .copied()
+            .filter(on(AlertChannel::Webhook))
+            .collect();
+        send_webhooks(url, template, &previous, &webhook_alerts);
+    }
+    emit_cycle_events(output, &events_path, &agent_id, &previous, &fresh_results);
+
+    let update = HealthUpdate {
+        timestamp,
+        results: health_results.clone(),
+    };
+    if let Err(e) = handle.emit("health-update", &update) {
+        warn!("Failed to emit health-update: {}", e);
     }
+
+    // Update Icon using helper
+    update_tray_icon(handle, &icon_set, template, status);
+    // A pause while this cycle ran wins over its results
+    if status != TrayStatus::Paused {
+        set_tray_down_count(handle, &health_results);
+        set_tray_tooltip(
+            handle,
+            &tray_tooltip(&health_results, Some(timestamp), false),
+        );
+    }
+
+    // Update Menu
+    set_tray_menu(handle, &entries);
+
+    health_results
 }
 
 #[tauri::command]
-fn add_service(
+fn set_webhook_url(
     state: State<AppState>,
-    name: String,
-    ip: String,
-    port: String,
-) -> Result<Vec<Service>, String> {
-    let mut data = state.data.lock().map_err(|_| "Failed to lock state")?;
-    data.services.push(Service { name, ip, port });
+    url: Option<String>,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+    if let Some(u) = &url {
+        validate_webhook_url(u)?;
+    }
 
-    // Save
-    let path = state.file_path.lock().map_err(|_| "Failed to lock path")?;
-    save_state(&data, &path);
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.webhook_url = url;
 
-    Ok(data.services.clone())
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
+}
+
+fn validate_webhook_url(url: &str) -> Result<(), String> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err("Webhook URL must start with http:// or https://".to_string());
+    }
+    Ok(())
 }
 
 #[tauri::command]
-fn list_services(state: State<AppState>) -> Result<Vec<Service>, String> {
-    let data = state.data.lock().map_err(|_| "Failed to lock state")?;
-    Ok(data.services.clone())
+fn get_webhook_url(state: State<AppState>) -> Result<Option<String>, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.webhook_url.clone())
 }
 
+// Replaces the default webhook body with a template; None or an empty one restores it
 #[tauri::command]
-fn remove_service(state: State<AppState>, index: usize) -> Result<Vec<Service>, String> {
-    let mut data = state.data.lock().map_err(|_| "Failed to lock state")?;
-    if index < data.services.len() {
-        data.services.remove(index);
+fn set_webhook_template(
+    state: State<AppState>,
+    template: Option<String>,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    let template = template.filter(|t| !t.trim().is_empty());
+    if let Some(t) = &template {
+        render_webhook(Some(t), &sample_webhook_payload())?;
+    }
 
-        // Save
-        let path = state.file_path.lock().map_err(|_| "Failed to lock path")?;
-        save_state(&data, &path);
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.webhook_template = template;
 
-        Ok(data.services.clone())
-    } else {
-        Err("Index out of bounds".to_string())
-    }
+    persist(&state, &mut data, txn_id)?;
+
+    Ok(())
 }
 
 #[tauri::command]
-fn update_service(
+fn get_webhook_template(state: State<AppState>) -> Result<Option<String>, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.webhook_template.clone())
+}
+
+// Enables or disables the local API and sets its port, (re)starting it right away
+#[tauri::command]
+fn set_api_settings(
     state: State<AppState>,
-    index: usize,
-    name: String,
-    ip: String,
-    port: String,
-) -> Result<Vec<Service>, String> {
-    let mut data = state.data.lock().map_err(|_| "Failed to lock state")?;
-    if index < data.services.len() {
-        data.services[index] = Service { name, ip, port };
+    enabled: bool,
+    port: u16,
+    txn_id: Option<u64>,
+) -> Result<(), String> {
+    if port == 0 {
+        return Err("API port must be between 1 and 65535".to_string());
+    }
 
-        // Save
-        let path = state.file_path.lock().map_err(|_| "Failed to lock path")?;
-        save_state(&data, &path);
+    {
+        let mut data = lock_or_recover(&state.data);
+        check_edit_txn(&mut data, txn_id)?;
+        data.api_enabled = enabled;
+        data.api_port = port;
 
-        Ok(data.services.clone())
-    } else {
-        Err("Index out of bounds".to_string())
+        persist(&state, &mut data, txn_id)?;
     }
+
+    configure_api_server(&state)
+}
+
+#[derive(Serialize)]
+struct ApiSettings {
+    enabled: bool,
+    port: u16,
+    running: bool, // False when enabled but the port could not be bound
 }
 
 #[tauri::command]
-fn set_interval(state: State<AppState>, interval: u64) -> Result<(), String> {
-    let mut data = state.data.lock().map_err(|_| "Failed to lock state")?;
-    data.interval_secs = interval;
+fn get_api_settings(state: State<AppState>) -> Result<ApiSettings, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(ApiSettings {
+        enabled: data.api_enabled,
+        port: data.api_port,
+        running: lock_or_recover(&state.api_server).is_some(),
+    })
+}
 
-    // Save
-    let path = state.file_path.lock().map_err(|_| "Failed to lock path")?;
-    save_state(&data, &path);
+// The release manifest at update_url, e.g. {"version": "1.2.0", "notes": "...", "url": "..."}
+#[derive(Deserialize)]
+struct UpdateManifest {
+    version: String,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    url: Option<String>, // Download page
+}
 
-    Ok(())
+// A release newer than the running one; also the payload of the "update-available" event
+#[derive(Clone, Serialize)]
+struct UpdateInfo {
+    version: String,
+    notes: String,
+    url: Option<String>,
+}
+
+const UPDATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Some(release) when the manifest names a newer version than `current`
+fn fetch_update(url: &str, current: &semver::Version) -> Result<Option<UpdateInfo>, String> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(UPDATE_TIMEOUT))
+        .build()
+        .into();
+    let body = agent
+        .get(url)
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+        .map_err(|e| format!("Update check of {} failed: {}", url, e))?;
+    let manifest: UpdateManifest =
+        serde_json::from_str(&body).map_err(|e| format!("Invalid update manifest: {}", e))?;
+    let latest = semver::Version::parse(manifest.version.trim_start_matches('v')).map_err(|e| {
+        format!(
+            "Invalid version '{}' in update manifest: {}",
+            manifest.version, e
+        )
+    })?;
+
+    Ok((latest > *current).then(|| UpdateInfo {
+        version: latest.to_string(),
+        notes: manifest.notes,
+        url: manifest.url,
+    }))
+}
+
+// Looks for a newer release in the background and emits "update-available" if there is one.
+// Failures only log, an unreachable manifest must never get in the way of monitoring.
+fn check_for_update_in_background(app: &tauri::AppHandle, url: String) {
+    let app = app.clone();
+    thread::spawn(
+        move || match fetch_update(&url, &app.package_info().version) {
+            Ok(Some(update)) => {
+                info!("Update available: {}", update.version);
+                if let Err(e) = app.emit("update-available", &update) {
+                    warn!("Failed to emit update-available: {}", e);
+                }
+            }
+            Ok(None) => info!("Uptime Watcher is up to date"),
+            Err(e) => warn!("{}", e),
+        },
+    );
 }
 
 #[tauri::command]
-fn get_interval(state: State<AppState>) -> Result<u64, String> {
-    let data = state.data.lock().map_err(|_| "Failed to lock state")?;
-    Ok(data.interval_secs)
+async fn check_for_update(app: tauri::AppHandle) -> Result<Option<UpdateInfo>, String> {
+    let url = {
+        let state = app.state::<AppState>();
+        let data = lock_or_recover(&state.data);
+        data.update_url.clone().ok_or("No update URL is set")?
+    };
+    let current = app.package_info().version.clone();
+    tauri::async_runtime::spawn_blocking(move || fetch_update(&url, &current))
+        .await
+        .map_err(|e| format!("Update check failed: {}", e))?
 }
 
 #[tauri::command]
-fn set_icon_set(
-    app: tauri::AppHandle,
+fn set_update_url(
     state: State<AppState>,
-    preference: String,
+    url: Option<String>,
+    txn_id: Option<u64>,
 ) -> Result<(), String> {
-    // println!(
-    //     "Command 'set_icon_set' invoked with preference: {}",
-    //     preference
-    // );
-    let mut data = state.data.lock().map_err(|_| "Failed to lock state")?;
-    data.icon_set = preference.clone();
+    let url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+    if let Some(u) = &url {
+        if !u.starts_with("http://") && !u.starts_with("https://") {
+            return Err("Update URL must start with http:// or https://".to_string());
+        }
+    }
 
-    // Immediate Update using current health state
-    update_tray_icon(&app, &preference, data.is_healthy);
+    let mut data = lock_or_recover(&state.data);
+    check_edit_txn(&mut data, txn_id)?;
+    data.update_url = url;
 
-    // Save
-    let path = state.file_path.lock().map_err(|_| "Failed to lock path")?;
-    save_state(&data, &path);
+    persist(&state, &mut data, txn_id)?;
 
     Ok(())
 }
 
 #[tauri::command]
-fn get_icon_set(state: State<AppState>) -> Result<String, String> {
-    let data = state.data.lock().map_err(|_| "Failed to lock state")?;
-    Ok(data.icon_set.clone())
+fn get_update_url(state: State<AppState>) -> Result<Option<String>, String> {
+    let data = lock_or_recover(&state.data);
+    Ok(data.update_url.clone())
 }
 
-// Returns a vector of tuples: (Service, is_healthy)
-fn check_lab_status(services: &[Service]) -> Vec<(Service, bool)> {
-    let mut results = Vec::new();
-
-    for service in services {
-        let address = format!("{}:{}", service.ip, service.port);
-        // Timeout set to 2 seconds
-        let is_healthy = TcpStream::connect_timeout(
-            &address.parse().unwrap_or("0.0.0.0:0".parse().unwrap()),
-            Duration::from_secs(2),
+// Sends a sample transition to the configured webhook and reports whether it was accepted
+#[tauri::command]
+async fn test_webhook(app: tauri::AppHandle) -> Result<(), String> {
+    let (url, template) = {
+        let state = app.state::<AppState>();
+        let data = lock_or_recover(&state.data);
+        let url = data.webhook_url.clone().ok_or("No webhook URL is set")?;
+        (url, data.webhook_template.clone())
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        post_webhook(
+            &url,
+            template.as_deref(),
+            &WebhookPayload {
+                service: "Uptime Watcher test",
+                old_state: HealthState::Up,
+                new_state: HealthState::Down,
+                timestamp: now_secs(),
+                text: "🔔 Uptime Watcher webhook test".to_string(),
+            },
         )
-        .is_ok();
+    })
+    .await
+    .map_err(|e| format!("Webhook test failed: {}", e))?
+}
+
+// Renders a webhook URL and template with sample data and returns warnings about the body's
+// shape for Slack and Discord. Nothing is saved; with `send` the sample is also posted, and a
+// failed post is the error. Malformed JSON is an error either way.
+#[tauri::command]
+async fn validate_webhook(
+    url: String,
+    template: Option<String>,
+    send: bool,
+) -> Result<Vec<String>, String> {
+    let url = url.trim().to_string();
+    validate_webhook_url(&url)?;
+    let template = template.filter(|t| !t.trim().is_empty());
+    let payload = sample_webhook_payload();
+    let body = render_webhook(template.as_deref(), &payload)?;
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
+    let warnings = webhook_shape_warnings(&url, &json);
+    if send {
+        tauri::async_runtime::spawn_blocking(move || {
+            post_webhook(&url, template.as_deref(), &payload)
+        })
+        .await
+        .map_err(|e| format!("Webhook test failed: {}", e))??;
+    }
+    Ok(warnings)
+}
+
+// Checks a host and port once, without retries and without adding or saving anything.
+// Ok(false) means it answered but failed the check, e.g. an HTTP 500.
+#[tauri::command]
+async fn test_service(
+    app: tauri::AppHandle,
+    ip: String,
+    port: String,
+    timeout_ms: Option<u64>,
+    protocol: Option<Protocol>,
+) -> Result<bool, String> {
+    let (host, port) = (ip.trim().to_string(), port.trim().to_string());
+    validate_service("test", &host, &port)?;
+    let settings = {
+        let state = app.state::<AppState>();
+        let data = lock_or_recover(&state.data);
+        check_settings(&data)
+    };
+    let service = Service {
+        name: "test".into(),
+        host,
+        port,
+        protocol: protocol.unwrap_or_default(),
+        ..Default::default()
+    };
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(settings.default_timeout_ms));
 
-        if !is_healthy {
-            println!("❌ {} ({}) is DOWN", service.name, address);
+    tauri::async_runtime::spawn_blocking(move || {
+        // A plain TCP probe doesn't say why it failed, so connect here to get the error
+        if service.protocol == Protocol::Tcp {
+            let addrs = resolve(&service.host, &service.port)?;
+            return connect_any(&addrs, timeout).map(|_| true);
+        }
+        match probe(&service, timeout, settings) {
+            (_, _, Some(e), _, _) => Err(e),
+            (healthy, _, None, _, _) => Ok(healthy),
         }
+    })
+    .await
+    .map_err(|e| format!("Test failed: {}", e))?
+}
 
-        results.push((service.clone(), is_healthy));
+// Plain-language reason for a failed connection
+fn describe_io_error(e: &std::io::Error) -> &'static str {
+    match e.kind() {
+        ErrorKind::TimedOut | ErrorKind::WouldBlock => "timed out, packets may be dropped",
+        ErrorKind::ConnectionRefused => "refused, nothing is listening on that port",
+        ErrorKind::HostUnreachable => "host unreachable",
+        ErrorKind::NetworkUnreachable => "network unreachable, check the route or VPN",
+        ErrorKind::ConnectionReset => "reset by the host",
+        ErrorKind::ConnectionAborted => "aborted",
+        ErrorKind::PermissionDenied => "not permitted, possibly by a local firewall",
+        ErrorKind::AddrNotAvailable => "address not available on this machine",
+        _ => "failed",
     }
+}
 
-    // Only print if everything is okay
-    if results.iter().all(|(_, healthy)| *healthy) && !results.is_empty() {
-        println!("✅ All Systems Normal");
+// Runs a service's check step by step (DNS, each TCP connect, then the protocol check) and
+// reports what happened at each one, for working out why a service shows as down
+#[tauri::command]
+async fn diagnose_service(app: tauri::AppHandle, index: usize) -> Result<String, String> {
+    let (service, settings) = {
+        let state = app.state::<AppState>();
+        let data = lock_or_recover(&state.data);
+        let service = data
+            .services
+            .get(index)
+            .cloned()
+            .ok_or("Index out of bounds")?;
+        (service, check_settings(&data))
+    };
+    let timeout = Duration::from_millis(service.timeout_ms.unwrap_or(settings.default_timeout_ms));
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut report = vec![format!(
+            "Service: {} ({:?} {}), timeout {} ms",
+            service.name,
+            service.protocol,
+            service_address(&service),
+            timeout.as_millis()
+        )];
+
+        if service.protocol != Protocol::Command {
+            let start = Instant::now();
+            let addrs = match resolve(&service.host, &service.port) {
+                Ok(addrs) => addrs,
+                Err(e) => {
+                    report.push(format!("DNS: {} ({} ms)", e, start.elapsed().as_millis()));
+                    return report.join("\n");
+                }
+            };
+            let list: Vec<String> = addrs.iter().map(|a| a.ip().to_string()).collect();
+            report.push(format!(
+                "DNS: {} resolved to {} in {} ms",
+                service.host,
+                list.join(", "),
+                start.elapsed().as_millis()
+            ));
+
+            // Ping and UDP don't use TCP, a connect would only add noise
+            if !matches!(service.protocol, Protocol::Ping | Protocol::Udp) {
+                for addr in &addrs {
+                    let start = Instant::now();
+                    let connect_timeout = split_timeouts(&service, timeout).0;
+                    let outcome = match TcpStream::connect_timeout(addr, connect_timeout) {
+                        Ok(_) => "connected".to_string(),
+                        Err(e) => format!("{} ({})", describe_io_error(&e), e),
+                    };
+                    report.push(format!(
+                        "TCP {}: {} in {} ms",
+                        addr,
+                        outcome,
+                        start.elapsed().as_millis()
+                    ));
+                }
+            }
+        }
+
+        let start = Instant::now();
+        let (healthy, status_code, error, cert_expires_at, output) =
+            probe(&service, timeout, settings);
+        let mut check = format!("Check: {}", if healthy { "up" } else { "down" });
+        if let Some(code) = status_code {
+            check.push_str(&format!(", HTTP {}", code));
+        }
+        if let Some(expires_at) = cert_expires_at {
+            check.push_str(&format!(
+                ", certificate expires in {} days",
+                cert_days_left(expires_at)
+            ));
+        }
+        if let Some(e) = error {
+            check.push_str(&format!(", {}", e));
+        }
+        report.push(format!("{} in {} ms", check, start.elapsed().as_millis()));
+        if let Some(output) = output {
+            report.push(format!("Output:\n{}", output));
+        }
+        report.join("\n")
+    })
+    .await
+    .map_err(|e| format!("Diagnosis failed: {}", e))
+}
+
+// Checks every service immediately; scheduled checks then count their interval from here
+#[tauri::command]
+async fn check_now(app: tauri::AppHandle) -> Result<Vec<(Service, bool)>, String> {
+    {
+        let state = app.state::<AppState>();
+        let data = lock_or_recover(&state.data);
+        if data.monitoring_paused {
+            return Err("Monitoring is paused".to_string());
+        }
     }
+    let results = tauri::async_runtime::spawn_blocking(move || {
+        run_check_cycle(&app, &app.state::<AppState>(), CycleScope::All)
+    })
+    .await
+    .map_err(|e| format!("Check failed: {}", e))?;
 
-    results
+    Ok(results
+        .into_iter()
+        .map(|r| (r.service, r.healthy))
+        .collect())
+}
+
+// Checks only the services that are currently down, e.g. right after fixing them, and
+// returns each of them with whether it is up now
+#[tauri::command]
+async fn recheck_down(app: tauri::AppHandle) -> Result<Vec<(Service, bool)>, String> {
+    let down: Vec<Service> = {
+        let state = app.state::<AppState>();
+        let data = lock_or_recover(&state.data);
+        if data.monitoring_paused {
+            return Err("Monitoring is paused".to_string());
+        }
+        data.last_results
+            .iter()
+            .filter(|r| !r.healthy)
+            .map(|r| r.service.clone())
+            .collect()
+    };
+    if down.is_empty() {
+        return Ok(Vec::new());
+    }
+    let results = tauri::async_runtime::spawn_blocking(move || {
+        run_check_cycle(&app, &app.state::<AppState>(), CycleScope::Down)
+    })
+    .await
+    .map_err(|e| format!("Check failed: {}", e))?;
+
+    Ok(results
+        .into_iter()
+        .filter(|r| down.iter().any(|s| same_service(s, &r.service)))
+        .map(|r| (r.service, r.healthy))
+        .collect())
+}
+
+// Remembers the window's geometry so the next show puts it back there
+fn save_window_geometry(window: &tauri::Window) {
+    let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+        return;
+    };
+    let state = window.state::<AppState>();
+    let mut data = lock_or_recover(&state.data);
+    data.window_geometry = Some(WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    });
+    if let Err(e) = check_edit_txn(&mut data, None).and_then(|_| persist(&state, &mut data, None)) {
+        error!("{}", e);
+    }
+}
+
+// Applies the saved geometry, pulled back onto a monitor if that one is gone or rearranged
+fn restore_window_geometry(window: &tauri::WebviewWindow) {
+    let geometry = lock_or_recover(&window.state::<AppState>().data).window_geometry;
+    let Some(g) = geometry else {
+        return;
+    };
+    let _ = window.set_size(tauri::PhysicalSize::new(g.width, g.height));
+
+    let monitors = window.available_monitors().unwrap_or_default();
+    let (center_x, center_y) = (
+        g.x.saturating_add((g.width / 2) as i32),
+        g.y.saturating_add((g.height / 2) as i32),
+    );
+    let contains_center = |m: &&tauri::Monitor| {
+        let (pos, size) = (m.position(), m.size());
+        (pos.x..pos.x + size.width as i32).contains(&center_x)
+            && (pos.y..pos.y + size.height as i32).contains(&center_y)
+    };
+    let monitor = monitors
+        .iter()
+        .find(contains_center)
+        .cloned()
+        .or_else(|| window.primary_monitor().ok().flatten())
+        .or_else(|| monitors.first().cloned());
+    let Some(monitor) = monitor else {
+        return;
+    };
+
+    // Keep the whole window inside the monitor, or at least its top-left corner if it's larger
+    let (pos, size) = (monitor.position(), monitor.size());
+    let x =
+        g.x.min(pos.x + size.width as i32 - g.width as i32)
+            .max(pos.x);
+    let y =
+        g.y.min(pos.y + size.height as i32 - g.height as i32)
+            .max(pos.y);
+    let _ = window.set_position(tauri::PhysicalPosition::new(x, y));
 }
 
 fn main() {
     tauri::Builder::default()
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_autostart::init(
+            MacosLauncher::LaunchAgent,
+            None,
+        ))
         .setup(|app| {
             // Set Activation Policy to Accessory (No Dock Icon, No App Switcher)
             app.set_activation_policy(tauri::ActivationPolicy::Accessory);
 
-            // 1. Resolve Config Path
-            let app_context = app.path();
-            let app_data_dir = app_context
-                .app_data_dir()
-                .unwrap_or_else(|_| PathBuf::from("."));
+            // 1. Resolve Config Path. UPTIME_CONFIG_PATH moves the settings, and the logs,
+            // history and events kept next to them, e.g. to a test fixture.
+            let config_override = std::env::var_os(CONFIG_PATH_ENV)
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from);
+            let app_data_dir = match &config_override {
+                Some(path) => path
+                    .parent()
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."))
+                    .to_path_buf(),
+                None => app
+                    .path()
+                    .app_data_dir()
+                    .unwrap_or_else(|_| PathBuf::from(".")),
+            };
 
             if !app_data_dir.exists() {
                 let _ = fs::create_dir_all(&app_data_dir);
             }
 
-            let file_path = app_data_dir.join("settings.json");
-            println!("Configuration file: {:?}", file_path);
+            // Logs go to stdout and a rotating file next to the settings
+            app.handle().plugin(
+                tauri_plugin_log::Builder::new()
+                    .targets([
+                        Target::new(TargetKind::Stdout),
+                        Target::new(TargetKind::Folder {
+                            path: app_data_dir.clone(),
+                            file_name: Some(LOG_FILE_NAME.to_string()),
+                        }),
+                    ])
+                    .rotation_strategy(RotationStrategy::KeepSome(5))
+                    .max_file_size(LOG_MAX_BYTES)
+                    .level(log::LevelFilter::Info)
+                    .build(),
+            )?;
+
+            // Large service lists need more sockets than the default limit on some systems
+            raise_fd_limit();
+
+            let file_path = config_override.unwrap_or_else(|| app_data_dir.join("settings.json"));
+            info!("Configuration file: {:?}", file_path);
+            prune_history(&history_path(&file_path));
 
             // 2. Load State or Default
+            // A first run starts with no services so the frontend can show its welcome screen
+            let first_run = !file_path.exists();
+            if first_run {
+                info!("First run, starting with no services");
+            }
             let mut initial_data = AppStateData {
-                services: vec![
-                    Service {
-                        name: "Google DNS".into(),
-                        ip: "8.8.8.8".into(),
-                        port: "53".into(),
-                    },
-                    Service {
-                        name: "Localhost HTTP".into(),
-                        ip: "127.0.0.1".into(),
-                        port: "80".into(),
-                    },
-                ],
+                services: Vec::new(),
                 interval_secs: 10,
                 icon_set: default_icon_set(),
+                template_mode: None,
+                services_source_file: None,
+                tray_show_all: true,
+                menu_name_max_len: None,
+                stabilization_secs: 0,
+                verbose_logging: false,
+                notifications_enabled: true,
+                notification_min_interval_secs: default_notification_min_interval_secs(),
+                snooze_until: None,
+                allow_command_checks: false,
+                sound_enabled: false,
+                webhook_url: None,
+                webhook_template: None,
+                update_url: None,
+                autostart: false,
+                monitoring_paused: false,
+                allow_duplicates: false,
+                health_policy: HealthPolicy::Any,
+                structured_event_output: EventOutput::Off,
+                agent_id: new_agent_id(),
+                default_timeout_ms: default_timeout_ms(),
+                cert_warn_days: default_cert_warn_days(),
+                retry_count: default_retry_count(),
+                retry_delay_ms: default_retry_delay_ms(),
+                max_concurrent_checks: default_max_concurrent_checks(),
+                down_recheck_secs: default_down_recheck_secs(),
+                check_jitter_ms: 0,
+                api_enabled: false,
+                api_port: default_api_port(),
+                window_geometry: None,
                 is_healthy: true,
+                first_run,
+                settings_backup: None,
+                services_source_error: None,
+                mirrors: HashMap::new(),
+                faults: HashMap::new(),
+                latency_samples: HashMap::new(),
+                escalations: HashMap::new(),
+                edit_txn: None,
+                last_txn_id: 0,
+                last_results: Vec::new(),
+                last_check_ts: None,
+                cycle_revision: 0,
+                stats_saved_at: 0,
+                stabilizing_since: None,
             };
 
             if file_path.exists() {
@@ -278,24 +6744,66 @@ fn main() {
                     Ok(content) => {
                         match serde_json::from_str::<AppStateData>(&content) {
                             Ok(saved_data) => {
-                                println!("Loaded settings from disk.");
+                                info!("Loaded settings from disk.");
                                 initial_data = saved_data;
                                 // Reset runtime flag just in case
                                 initial_data.is_healthy = true;
+                                // Older versions accepted any interval, including 0
+                                initial_data.interval_secs = initial_data
+                                    .interval_secs
+                                    .clamp(MIN_INTERVAL_SECS, MAX_INTERVAL_SECS);
+                                // A hand-edited file can hold a limit below the minimum
+                                initial_data.menu_name_max_len = initial_data
+                                    .menu_name_max_len
+                                    .map(|len| len.max(MIN_MENU_NAME_LEN));
+                                // Older versions stored any retry policy
+                                initial_data.retry_count =
+                                    initial_data.retry_count.min(MAX_RETRY_COUNT);
+                                initial_data.retry_delay_ms =
+                                    initial_data.retry_delay_ms.min(MAX_RETRY_DELAY_MS);
                             }
                             Err(e) => {
-                                println!("Failed to deserialize settings: {}", e);
+                                error!("Failed to deserialize settings: {}", e);
+                                // Keep the broken file for hand recovery, the next save would
+                                // otherwise overwrite it with the defaults
+                                match backup_corrupt_settings(&file_path) {
+                                    Ok(backup) => {
+                                        warn!("Settings reset, previous file kept at {:?}", backup);
+                                        initial_data.settings_backup =
+                                            Some(backup.to_string_lossy().into_owned());
+                                    }
+                                    Err(e) => error!("{}", e),
+                                }
                             }
                         }
                     }
-                    Err(e) => println!("Failed to read settings file: {}", e),
+                    Err(e) => error!("Failed to read settings file: {}", e),
                 }
             }
 
             // 3. Init State
+            let source_file = initial_data.services_source_file.clone();
+            let shared_data = Arc::new(Mutex::new(initial_data));
+
+            // An external services file overrides the saved list and is watched for changes
+            let source_watcher = source_file.and_then(|path| {
+                let path = PathBuf::from(path);
+                reload_services_source(&shared_data, &path);
+                match watch_services_source(shared_data.clone(), path) {
+                    Ok(watcher) => Some(watcher),
+                    Err(e) => {
+                        error!("{}", e);
+                        None
+                    }
+                }
+            });
+
             let app_state = AppState {
-                data: Arc::new(Mutex::new(initial_data)),
+                data: shared_data,
                 file_path: Arc::new(Mutex::new(file_path)),
+                source_watcher: Mutex::new(source_watcher),
+                running: Arc::new(AtomicBool::new(true)),
+                api_server: Mutex::new(None),
             };
 
             // Manage state manually since we are inside setup?
@@ -303,120 +6811,106 @@ fn main() {
             // But we can call app.manage() inside setup for tauri v1.
             // In v2 check docs, but app.manage() should work on AppHandle or App.
             app.manage(app_state);
+            if let Some(window) = app.get_webview_window("main") {
+                restore_window_geometry(&window);
+            }
 
             // Create initial menu
-            let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let show_i = MenuItem::with_id(app, "show", "Manage Services", true, None::<&str>)?;
-            let sep = PredefinedMenuItem::separator(app)?;
-            let menu = Menu::with_items(app, &[&show_i, &sep, &quit_i])?;
+            let menu = build_tray_menu(app, &menu_model(&[], &[], None, None, false, true))?;
 
             let _tray = TrayIconBuilder::with_id("main")
                 .icon(app.default_window_icon().unwrap().clone())
                 .menu(&menu)
                 .show_menu_on_left_click(true)
                 .on_menu_event(|app, event| match event.id.as_ref() {
-                    "quit" => app.exit(0),
+                    "quit" => {
+                        // Stop the loop, then save once more (for the service stats) before exiting
+                        let state = app.state::<AppState>();
+                        state.running.store(false, Ordering::SeqCst);
+                        let mut data = lock_or_recover(&state.data);
+                        // An edit that was never committed is dropped, not saved half-done
+                        rollback_edit(&mut data);
+                        if let Err(e) = save_state(&data, &lock_or_recover(&state.file_path)) {
+                            error!("{}", e);
+                        }
+                        app.exit(0);
+                    }
+                    "pause" | "resume" => {
+                        let state = app.state::<AppState>();
+                        let paused = event.id.as_ref() == "pause";
+                        if let Err(e) = set_monitoring_paused(app, &state, paused, None) {
+                            error!("{}", e);
+                        }
+                    }
+                    "recheck_down" => {
+                        let app = app.clone();
+                        tauri::async_runtime::spawn_blocking(move || {
+                            run_check_cycle(&app, &app.state::<AppState>(), CycleScope::Down);
+                        });
+                    }
+                    "cycle_icons" => {
+                        if let Err(e) = cycle_icon_set(app) {
+                            error!("{}", e);
+                        }
+                    }
                     "show" => {
                         if let Some(window) = app.get_webview_window("main") {
+                            restore_window_geometry(&window);
                             let _ = window.show();
                             let _ = window.set_focus();
                         }
                     }
-                    _ => {}
+                    id => {
+                        if let Some(Ok(index)) = id.strip_prefix("copy:").map(str::parse) {
+                            copy_service_address(app, index);
+                        } else if let Some(Ok(index)) = id.strip_prefix("open:").map(str::parse) {
+                            open_service_url(app, index);
+                        }
+                    }
                 })
                 .build(app)?;
 
-            let handle = app.handle().clone();
-            // Get a reference to the state to pass to the thread
+            // The check loop never touches the tray while paused, so show that now
             let state = app.state::<AppState>();
-            let shared_data = state.data.clone();
+            let data = lock_or_recover(&state.data);
+            if data.monitoring_paused {
+                refresh_tray(app.handle(), &data);
+            }
+            drop(data);
+            if let Err(e) = configure_api_server(&state) {
+                error!("{}", e);
+            }
+            // The login item can go missing, e.g. when the app was moved, so register it again
+            let autostart = lock_or_recover(&state.data).autostart;
+            if autostart {
+                if let Err(e) = apply_autostart(app.handle(), true) {
+                    warn!("{}", e);
+                }
+            }
+            let update_url = lock_or_recover(&state.data).update_url.clone();
+            if let Some(url) = update_url {
+                check_for_update_in_background(app.handle(), url);
+            }
 
+            let handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                let mut last_check = Instant::now();
-                // Hack: subtract a large duration to force immediate check
-                last_check = last_check - Duration::from_secs(3600);
-
-                loop {
-                    // 1. Get current interval and service list
-                    let (interval, services, icon_set) = {
-                        let data = shared_data.lock().unwrap();
-                        (
-                            data.interval_secs,
-                            data.services.clone(),
-                            data.icon_set.clone(),
-                        )
-                    };
-
-                    // 2. Check if it's time to run
-                    if last_check.elapsed() >= Duration::from_secs(interval) {
-                        let health_results = check_lab_status(&services);
-                        last_check = Instant::now();
-
-                        // Determine overall health (Red if ANY service is down)
-                        let is_overall_healthy = health_results.iter().all(|(_, healthy)| *healthy);
+                let state = handle.state::<AppState>();
+                while state.running.load(Ordering::SeqCst) {
+                    // Checks whichever services' intervals have passed, if any
+                    run_check_cycle(&handle, &state, CycleScope::Due);
 
-                        // Store current health status in state for immediate updates
-                        if let Ok(mut data) = shared_data.lock() {
-                            data.is_healthy = is_overall_healthy;
-                        }
-
-                        // Update Icon using helper
-                        update_tray_icon(&handle, &icon_set, is_overall_healthy);
-
-                        // Update Menu
-                        if let Some(tray) = handle.tray_by_id("main") {
-                            let show_i = MenuItem::with_id(
-                                &handle,
-                                "show",
-                                "Manage Services",
-                                true,
-                                None::<&str>,
-                            );
-                            let quit_i =
-                                MenuItem::with_id(&handle, "quit", "Quit", true, None::<&str>);
-                            let sep = PredefinedMenuItem::separator(&handle);
-
-                            if let (Ok(show), Ok(quit), Ok(sep)) = (show_i, quit_i, sep) {
-                                let mut items: Vec<Box<dyn tauri::menu::IsMenuItem<tauri::Wry>>> =
-                                    vec![Box::new(show), Box::new(sep.clone())];
-
-                                for (svc, healthy) in &health_results {
-                                    let icon = if *healthy { "✅" } else { "❌" };
-                                    let text = format!("{} {}", icon, svc.name);
-                                    if let Ok(item) = MenuItem::with_id(
-                                        &handle,
-                                        "status",
-                                        &text,
-                                        false,
-                                        None::<&str>,
-                                    ) {
-                                        items.push(Box::new(item));
-                                    }
-                                }
-
-                                if let Ok(sep2) = PredefinedMenuItem::separator(&handle) {
-                                    items.push(Box::new(sep2));
-                                }
-                                items.push(Box::new(quit));
-
-                                let item_refs: Vec<&dyn tauri::menu::IsMenuItem<tauri::Wry>> =
-                                    items.iter().map(|b| b.as_ref()).collect();
-                                if let Ok(menu) = Menu::with_items(&handle, &item_refs) {
-                                    let _ = tray.set_menu(Some(menu));
-                                }
-                            }
-                        }
-                    }
-
-                    // Check every 1 second
-                    thread::sleep(Duration::from_secs(1));
+                    // Sleep until the next service is due rather than waking every second
+                    let delay = next_check_delay(&lock_or_recover(&state.data));
+                    thread::sleep(delay);
                 }
+                info!("Monitoring stopped");
             });
 
             Ok(())
         })
         .on_window_event(|window, event| {
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                save_window_geometry(window);
                 let _ = window.hide();
                 api.prevent_close();
             }
@@ -429,8 +6923,470 @@ fn main() {
             set_interval,
             get_interval,
             set_icon_set,
-            get_icon_set
+            get_icon_set,
+            set_services_source_file,
+            get_services_source,
+            mirror_service,
+            get_mirror_comparison,
+            stop_mirror,
+            set_menu_name_max_len,
+            get_menu_name_max_len,
+            set_fault_injection,
+            clear_fault_injection,
+            begin_edit,
+            commit_edit,
+            get_menu_text,
+            set_stabilization_secs,
+            get_stabilization_secs,
+            list_check_templates,
+            apply_template,
+            set_verbose_logging,
+            get_verbose_logging,
+            toggle_pin,
+            get_fd_usage,
+            set_structured_event_output,
+            get_structured_event_output,
+            get_last_latencies,
+            get_uptime_stats,
+            set_notifications_enabled,
+            get_notifications_enabled,
+            check_now,
+            set_service_enabled,
+            set_udp_payload,
+            set_tcp_exchange,
+            set_cert_warn_days,
+            get_cert_warn_days,
+            set_degraded_latency_ms,
+            set_retry_policy,
+            get_retry_policy,
+            move_service,
+            export_config,
+            import_config,
+            set_service_interval,
+            get_log_path,
+            get_overall_health,
+            set_max_concurrency,
+            get_max_concurrency,
+            snooze_notifications,
+            set_allow_duplicates,
+            get_allow_duplicates,
+            clear_services,
+            list_icon_sets,
+            set_template_mode,
+            get_template_mode,
+            set_down_recheck_secs,
+            get_down_recheck_secs,
+            is_first_run,
+            pause_monitoring,
+            resume_monitoring,
+            is_monitoring_paused,
+            set_expected_body,
+            get_service_stats,
+            reset_stats,
+            set_webhook_url,
+            get_webhook_url,
+            test_webhook,
+            set_default_timeout_ms,
+            get_default_timeout_ms,
+            test_service,
+            set_service_group,
+            set_sound_enabled,
+            get_sound_enabled,
+            add_command_service,
+            set_service_command,
+            set_allow_command_checks,
+            get_allow_command_checks,
+            set_api_settings,
+            get_api_settings,
+            set_check_jitter_ms,
+            get_check_jitter_ms,
+            set_maintenance_windows,
+            get_config_path,
+            get_settings_reset,
+            add_services,
+            get_downtime,
+            set_service_critical,
+            set_health_policy,
+            get_health_policy,
+            load_services_from_file,
+            diagnose_service,
+            get_app_info,
+            check_for_update,
+            set_update_url,
+            get_update_url,
+            set_tray_show_all,
+            get_tray_show_all,
+            set_autostart,
+            get_autostart,
+            reload_settings,
+            get_service,
+            set_service_notify,
+            recheck_down,
+            get_summary,
+            set_service_timeouts,
+            set_notification_min_interval_secs,
+            get_notification_min_interval_secs,
+            duplicate_service,
+            abort_edit,
+            get_outage_timing,
+            set_quiet_hours,
+            get_quiet_hours,
+            set_healthz,
+            get_healthz,
+            get_latency_percentiles,
+            set_adaptive_timeout,
+            get_status_packed,
+            set_webhook_template,
+            get_webhook_template,
+            validate_webhook,
+            escalate_service,
+            get_escalations,
+            get_service_detail,
+            set_service_webhook,
+            set_secondary_check,
+            get_secondary_check
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_summary_leaves_out_secrets() {
+        let data: AppStateData = serde_json::from_value(serde_json::json!({
+            "services": [{
+                "name": "Internal API",
+                "host": "secret-host.internal",
+                "port": "8443",
+                "command": "curl -H 'Authorization: Bearer hunter2' localhost",
+            }],
+            "interval_secs": 60,
+            "webhook_url": "https://hooks.example.com/services/T000/B000/webhook-token",
+            "update_url": "https://updates.example.com/manifest.json?key=update-token",
+        }))
+        .unwrap();
+        let json = serde_json::to_string_pretty(&data).unwrap();
+
+        let summary = state_summary(&data, &json);
+
+        for secret in [
+            "hooks.example.com",
+            "webhook-token",
+            "update-token",
+            "secret-host.internal",
+            "hunter2",
+        ] {
+            assert!(!summary.contains(secret), "{} leaked: {}", secret, summary);
+        }
+        assert!(summary.starts_with("1 services, "));
+    }
+
+    #[test]
+    fn latency_percentiles_of_a_known_window() {
+        // 1..=100 ms in shuffled order
+        let window: VecDeque<u64> = (1..=100).map(|i| i * 37 % 101).collect();
+        assert_eq!(
+            latency_percentiles("api", Some(&window)),
+            LatencyPercentiles {
+                name: "api".to_string(),
+                p50: Some(50),
+                p90: Some(90),
+                p99: Some(99),
+                samples: 100,
+            }
+        );
+
+        let few: VecDeque<u64> = VecDeque::from([120, 80, 300]);
+        let stats = latency_percentiles("slow", Some(&few));
+        assert_eq!(
+            (stats.p50, stats.p90, stats.p99),
+            (Some(120), Some(300), Some(300))
+        );
+
+        let empty = latency_percentiles("new", None);
+        assert_eq!((empty.p50, empty.samples), (None, 0));
+    }
+
+    #[test]
+    fn adaptive_timeout_scales_the_p95_latency() {
+        let adaptive = AdaptiveTimeout {
+            factor: 3.0,
+            min_ms: 200,
+            max_ms: 5000,
+        };
+        // 95 checks at 100 ms and 5 slow ones, so p95 is 100 ms
+        let mut window: VecDeque<u64> = std::iter::repeat_n(100, 95).collect();
+        window.extend([900, 950, 1000, 1100, 1200]);
+        assert_eq!(adaptive_timeout_ms(&adaptive, Some(&window)), Some(300));
+
+        // Bounded on both sides
+        let fast: VecDeque<u64> = std::iter::repeat_n(10, 40).collect();
+        assert_eq!(adaptive_timeout_ms(&adaptive, Some(&fast)), Some(200));
+        let slow: VecDeque<u64> = std::iter::repeat_n(4000, 40).collect();
+        assert_eq!(adaptive_timeout_ms(&adaptive, Some(&slow)), Some(5000));
+
+        // The static timeout applies until there are enough samples
+        let few: VecDeque<u64> = std::iter::repeat_n(100, MIN_ADAPTIVE_SAMPLES - 1).collect();
+        assert_eq!(adaptive_timeout_ms(&adaptive, Some(&few)), None);
+        assert_eq!(adaptive_timeout_ms(&adaptive, None), None);
+    }
+
+    #[test]
+    fn packed_status_wire_format() {
+        let results = [
+            (true, Some(12)),
+            (false, None),
+            (true, Some(300)),
+            (true, Some(0)),
+            (true, Some(1)),
+            (true, Some(1)),
+            (true, Some(1)),
+            (true, Some(1)),
+            (false, None),
+        ];
+        let expected = [
+            &[1][..],                         // version
+            &[0xac, 0x02],                    // revision 300
+            &[0x80, 0xe2, 0xcf, 0xaa, 0x06],  // timestamp 1700000000
+            &[9],                             // services
+            &[0b1111_1101, 0b0000_0000],      // up bits, service 0 in the lowest bit
+            &[13, 0, 0xad, 0x02, 1, 2, 2, 2], // latency + 1, 0 = none
+            &[2, 0],
+        ]
+        .concat();
+        assert_eq!(pack_status(300, 1_700_000_000, &results), expected);
+    }
+
+    #[test]
+    fn redis_template_expects_pong() {
+        let exchange = CHECK_TEMPLATES
+            .iter()
+            .find(|t| t.name == "Redis")
+            .and_then(|t| t.exchange.clone())
+            .unwrap();
+        let server = |reply: &'static [u8]| {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 6];
+                stream.read_exact(&mut request).unwrap();
+                assert_eq!(&request, b"PING\r\n");
+                stream.write_all(reply).unwrap();
+            });
+            Service {
+                host: "127.0.0.1".into(),
+                port: port.to_string(),
+                tcp_exchange: Some(exchange.clone()),
+                ..Default::default()
+            }
+        };
+        let timeout = Duration::from_secs(2);
+
+        let service = server(b"+PONG\r\n");
+        assert_eq!(tcp_exchange_check(&service, &exchange, timeout), Ok(true));
+        let service = server(b"-NOAUTH Authentication required.\r\n");
+        assert_eq!(
+            tcp_exchange_check(&service, &exchange, timeout),
+            Err("Unexpected reply".to_string())
+        );
+    }
+
+    #[test]
+    fn pinned_services_lead_their_group() {
+        let service = |name: &str, group: Option<&str>, pinned: bool| Service {
+            name: name.into(),
+            group: group.map(String::from),
+            pinned,
+            ..Default::default()
+        };
+        let services = vec![
+            service("web", Some("Prod"), false),
+            service("nas", None, false),
+            service("db", Some("Prod"), true),
+            service("router", None, true),
+            service("ci", Some("Dev"), false),
+        ];
+        let results: Vec<CheckResult> = services
+            .iter()
+            .map(|service| CheckResult {
+                service: service.clone(),
+                healthy: true,
+                state: HealthState::Up,
+                latency_ms: Some(1),
+                status_code: None,
+                error: None,
+                cert_expires_at: None,
+                injected: false,
+                output: None,
+            })
+            .collect();
+        let texts: Vec<String> = menu_model(&results, &services, None, None, false, true)
+            .into_iter()
+            .filter_map(|entry| match entry {
+                MenuEntry::Item { text, .. } if text.starts_with('✅') || text.starts_with('—') => {
+                    Some(text)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                "✅ router",
+                "✅ nas",
+                "— Prod —",
+                "✅ db",
+                "✅ web",
+                "— Dev —",
+                "✅ ci"
+            ]
+        );
+    }
+
+    #[test]
+    fn untagged_change_merges_into_the_edit_snapshot() {
+        use serde_json::json;
+        let service = |name: &str, enabled: bool| json!({"name": name, "host": "10.0.0.1", "port": "80", "enabled": enabled});
+        // Staged in the edit: a new interval and db removed. Untagged: web paused and api added.
+        let base = json!({"interval_secs": 60, "paused": false, "services": [service("web", true), service("db", true)]});
+        let after = json!({"interval_secs": 60, "paused": true, "services": [service("web", false), service("db", true), service("api", true)]});
+        let mut snapshot =
+            json!({"interval_secs": 30, "paused": false, "services": [service("web", true)]});
+        merge_changes(&base, &after, &mut snapshot);
+        assert_eq!(
+            snapshot,
+            json!({"interval_secs": 30, "paused": true, "services": [service("web", false), service("api", true)]})
+        );
+    }
+
+    #[test]
+    fn secondary_step_failure_is_named() {
+        let primary: Attempt = (true, Some(200), None, None, None);
+        let combined = combine_steps(primary.clone(), "API JSON", || {
+            (
+                false,
+                Some(200),
+                Some("healthz $.status is \"fail\", expected \"ok\"".into()),
+                None,
+                None,
+            )
+        });
+        assert_eq!(
+            combined,
+            (
+                false,
+                Some(200),
+                Some(
+                    "step 'API JSON' failed: healthz $.status is \"fail\", expected \"ok\"".into()
+                ),
+                None,
+                None
+            )
+        );
+
+        let combined = combine_steps(primary, "API JSON", || (false, Some(503), None, None, None));
+        assert_eq!(
+            combined.2.as_deref(),
+            Some("step 'API JSON' failed: HTTP 503")
+        );
+    }
+
+    #[test]
+    fn secondary_step_waits_for_the_primary() {
+        let mut ran = false;
+        let combined = combine_steps((false, None, None, None, None), "API JSON", || {
+            ran = true;
+            (true, Some(200), None, None, None)
+        });
+        assert!(!ran);
+        assert_eq!(
+            combined,
+            (
+                false,
+                None,
+                Some("primary check failed: no answer".into()),
+                None,
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn both_steps_passing_keep_the_primary_result() {
+        let primary: Attempt = (true, None, None, Some(1_900_000_000), None);
+        let combined = combine_steps(primary.clone(), "API JSON", || {
+            (true, Some(200), None, None, None)
+        });
+        assert_eq!(combined, primary);
+    }
+
+    fn healthz(json_path: &str, expected: serde_json::Value) -> HealthzCheck {
+        HealthzCheck {
+            path: "/healthz".to_string(),
+            json_path: json_path.to_string(),
+            expected,
+        }
+    }
+
+    #[test]
+    fn healthz_passes_when_the_value_matches() {
+        let body = r#"{"status": "ok", "checks": {"db": {"status": "ok"}}}"#;
+        assert_eq!(
+            evaluate_healthz(body, &healthz("$.status", "ok".into())),
+            Ok(())
+        );
+        assert_eq!(
+            evaluate_healthz(body, &healthz("$.checks['db'].status", "ok".into())),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn healthz_names_the_failing_sub_checks() {
+        let body = r#"{
+            "status": "degraded",
+            "checks": {"db": {"status": "ok"}, "cache": {"status": "down"}, "queue": "down"}
+        }"#;
+        let reason = evaluate_healthz(body, &healthz("$.status", "ok".into())).unwrap_err();
+        assert_eq!(
+            reason,
+            r#"healthz $.status is "degraded", expected "ok" (failing: cache, queue)"#
+        );
+    }
+
+    #[test]
+    fn healthz_reports_missing_values_and_non_json() {
+        let check = healthz("$.components[1].up", true.into());
+        assert_eq!(
+            evaluate_healthz(r#"{"components": [{"up": true}]}"#, &check),
+            Err("healthz $.components[1].up not found".to_string())
+        );
+        assert_eq!(
+            evaluate_healthz(r#"{"components": [{"up": true}, {"up": true}]}"#, &check),
+            Ok(())
+        );
+        assert_eq!(
+            evaluate_healthz("OK", &check),
+            Err("healthz body is not JSON".to_string())
+        );
+    }
+
+    #[test]
+    fn json_path_subset() {
+        assert_eq!(
+            parse_json_path("$.checks[\"db\"][0]"),
+            Ok(vec![
+                JsonPathSegment::Key("checks".to_string()),
+                JsonPathSegment::Key("db".to_string()),
+                JsonPathSegment::Index(0),
+            ])
+        );
+        assert!(parse_json_path("status").is_err());
+        assert!(parse_json_path("$..status").is_err());
+        assert!(parse_json_path("$[x]").is_err());
+    }
+}
@@ -4,8 +4,9 @@ import "./App.css";
 
 interface Service {
   name: String;
-  ip: String;
+  host: String;
   port: String;
+  description: string;
 }
 
 function App() {
@@ -13,8 +14,14 @@ function App() {
   const [name, setName] = useState("");
   const [ip, setIp] = useState("");
   const [port, setPort] = useState("");
+  const [description, setDescription] = useState("");
   const [interval, setIntervalVal] = useState<number>(10);
   const [iconSet, setIconSet] = useState<string>("default");
+  const [iconSets, setIconSets] = useState<string[]>(["default", "alt"]);
+  const [templateMode, setTemplateMode] = useState<boolean>(false);
+  const [autostart, setAutostart] = useState<boolean>(false);
+  const [firstRun, setFirstRun] = useState<boolean>(false);
+  const [settingsBackup, setSettingsBackup] = useState<string | null>(null);
   
   // Track which index we are editing. -1 means adding new.
   const [editIndex, setEditIndex] = useState<number>(-1);
@@ -27,6 +34,16 @@ function App() {
       setIntervalVal(currentInterval);
       const currentIconSet = await invoke<string>("get_icon_set");
       setIconSet(currentIconSet);
+      const availableIconSets = await invoke<string[]>("list_icon_sets");
+      setIconSets(availableIconSets);
+      const currentTemplateMode = await invoke<boolean>("get_template_mode");
+      setTemplateMode(currentTemplateMode);
+      const currentAutostart = await invoke<boolean>("get_autostart");
+      setAutostart(currentAutostart);
+      const isFirstRun = await invoke<boolean>("is_first_run");
+      setFirstRun(isFirstRun);
+      const backup = await invoke<string | null>("get_settings_reset");
+      setSettingsBackup(backup);
     } catch (error) {
       console.error("Failed to fetch data:", error);
     }
@@ -46,6 +63,27 @@ function App() {
       }
   };
 
+  const handleTemplateModeChange = async (e: React.ChangeEvent<HTMLInputElement>) => {
+      const enabled = e.target.checked;
+      setTemplateMode(enabled);
+      try {
+          await invoke("set_template_mode", { enabled });
+      } catch (error) {
+          console.error("Failed to set template mode", error);
+      }
+  };
+
+  const handleAutostartChange = async (e: React.ChangeEvent<HTMLInputElement>) => {
+      const enabled = e.target.checked;
+      setAutostart(enabled);
+      try {
+          await invoke("set_autostart", { enabled });
+      } catch (error) {
+          console.error("Failed to set autostart", error);
+          setAutostart(!enabled);
+      }
+  };
+
   const handleIconSetChange = async (e: React.ChangeEvent<HTMLSelectElement>) => {
       const newSet = e.target.value;
       console.log("Frontend: Switching icon set to:", newSet);
@@ -72,6 +110,7 @@ function App() {
           name,
           ip,
           port,
+          description,
         });
       } else {
         // Edit Mode
@@ -80,6 +119,7 @@ function App() {
           name,
           ip,
           port,
+          description,
         });
         setEditIndex(-1); // Switch back to add mode
       }
@@ -88,17 +128,26 @@ function App() {
       setName("");
       setIp("");
       setPort("");
+      setDescription("");
     } catch (error) {
       console.error("Failed to save service:", error);
       alert("Failed to save service: " + error);
     }
   };
 
-  const handleEdit = (index: number) => {
-    const svc = services[index];
+  const handleEdit = async (index: number) => {
+    // Fetch the latest copy, the list may have changed since it was shown
+    let svc: Service;
+    try {
+      svc = await invoke<Service>("get_service", { index });
+    } catch (error) {
+      console.error("Failed to load service:", error);
+      return;
+    }
     setName(svc.name as string);
-    setIp(svc.ip as string);
+    setIp(svc.host as string);
     setPort(svc.port as string);
+    setDescription(svc.description);
     setEditIndex(index);
   };
 
@@ -112,23 +161,57 @@ function App() {
         setName("");
         setIp("");
         setPort("");
+        setDescription("");
       }
     } catch (error) {
       console.error("Failed to remove service:", error);
     }
   };
 
+  const handleDuplicate = async (index: number) => {
+    try {
+      const updated = await invoke<Service[]>("duplicate_service", { index });
+      setServices(updated);
+    } catch (error) {
+      console.error("Failed to duplicate service:", error);
+    }
+  };
+
+  const handleTest = async () => {
+    try {
+      const up = await invoke<boolean>("test_service", { ip, port });
+      alert(up ? `${ip}:${port} is reachable` : `${ip}:${port} answered but failed the check`);
+    } catch (error) {
+      alert(`${ip}:${port} is not reachable: ${error}`);
+    }
+  };
+
   const cancelEdit = () => {
     setEditIndex(-1);
     setName("");
     setIp("");
     setPort("");
+    setDescription("");
   };
 
   return (
     <main className="container">
       <h1>Uptime Watcher Services</h1>
 
+      {settingsBackup && (
+        <div className="glass-panel">
+          <h2>Settings were reset</h2>
+          <p>The settings file could not be read, so defaults are in use. The old file was kept at {settingsBackup}.</p>
+        </div>
+      )}
+
+      {firstRun && services.length === 0 && (
+        <div className="glass-panel">
+          <h2>Welcome to Uptime Watcher</h2>
+          <p>Add the first service you want to keep an eye on below. Its status will show up in the tray menu.</p>
+        </div>
+      )}
+
       <div className="settings-bar glass-panel">
           <div>
             <label>Check Interval: </label>
@@ -143,10 +226,25 @@ function App() {
           <div>
             <label>Icons: </label>
             <select value={iconSet} onChange={handleIconSetChange}>
-                <option value="default">Default (Green/Red)</option>
-                <option value="alt">Alternate (Check/Cross)</option>
+                {iconSets.map((set) => (
+                  <option key={set} value={set}>
+                    {set === "default" ? "Default (Green/Red)" : set === "alt" ? "Alternate (Check/Cross)" : set}
+                  </option>
+                ))}
             </select>
           </div>
+          <div>
+            <label>
+              <input type="checkbox" checked={templateMode} onChange={handleTemplateModeChange} />
+              Template icon (adapts to light/dark menu bar)
+            </label>
+          </div>
+          <div>
+            <label>
+              <input type="checkbox" checked={autostart} onChange={handleAutostartChange} />
+              Start at login
+            </label>
+          </div>
       </div>
 
       <div className="service-list">
@@ -158,13 +256,19 @@ function App() {
               <div className="service-info">
                 <span className="service-name">{svc.name}</span>
                 <span className="service-address">
-                  {svc.ip}:{svc.port}
+                  {svc.host}:{svc.port}
                 </span>
+                {svc.description && (
+                  <span className="service-description">{svc.description}</span>
+                )}
               </div>
               <div className="actions">
                 <button className="edit-btn" onClick={() => handleEdit(idx)}>
                   Edit
                 </button>
+                <button className="edit-btn" onClick={() => handleDuplicate(idx)}>
+                  Duplicate
+                </button>
                 <button className="delete-btn" onClick={() => handleRemove(idx)}>
                   Remove
                 </button>
@@ -186,7 +290,7 @@ function App() {
         <div className="form-row">
             <input
             className="input-group-ip"
-            placeholder="IP (e.g. 8.8.8.8)"
+            placeholder="Host (e.g. 8.8.8.8 or example.com)"
             value={ip}
             onChange={(e) => setIp(e.target.value)}
             />
@@ -197,10 +301,18 @@ function App() {
             onChange={(e) => setPort(e.target.value)}
             />
         </div>
+        <input
+          placeholder="Description (optional)"
+          value={description}
+          onChange={(e) => setDescription(e.target.value)}
+        />
         <div className="form-actions">
             <button type="submit" className={editIndex === -1 ? "add-btn" : "update-btn"}>
             {editIndex === -1 ? "Add Service" : "Update Service"}
             </button>
+            <button type="button" className="test-btn" onClick={handleTest}>
+                Test
+            </button>
             {editIndex !== -1 && (
                 <button type="button" className="cancel-btn" onClick={cancelEdit}>
                     Cancel